@@ -0,0 +1,252 @@
+// animation.rs
+use std::f32::consts::PI;
+
+use raylib::prelude::*;
+
+use crate::camera::Camera;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interpolation {
+    Linear,
+    CatmullRom, // smooth curve through every key, tangents from the neighbours
+    Bezier,     // cubic Bézier, handles from `eye_tangent`/`center_tangent` (Catmull-Rom if None)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    pub fn apply(self, u: f32) -> f32 {
+        let u = u.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => u,
+            Easing::EaseIn => u * u * u,
+            Easing::EaseOut => 1.0 - (1.0 - u).powi(3),
+            Easing::EaseInOut => u * u * (3.0 - 2.0 * u),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Keyframe {
+    pub time: f32, // seconds
+    pub eye: Vector3,
+    pub center: Vector3,
+    pub fov: f32,
+    pub focal_distance: f32,
+    pub easing: Easing, // easing of the segment that starts at this key
+    pub eye_tangent: Option<Vector3>,
+    pub center_tangent: Option<Vector3>,
+}
+
+impl Keyframe {
+    pub fn new(time: f32, eye: Vector3, center: Vector3, fov: f32) -> Self {
+        Keyframe {
+            time,
+            eye,
+            center,
+            fov,
+            focal_distance: (center - eye).length(),
+            easing: Easing::Linear,
+            eye_tangent: None,
+            center_tangent: None,
+        }
+    }
+}
+
+// Pose sampled from a path at some time
+#[derive(Clone, Copy, Debug)]
+pub struct CameraPose {
+    pub eye: Vector3,
+    pub center: Vector3,
+    pub fov: f32,
+    pub focal_distance: f32,
+}
+
+impl CameraPose {
+    pub fn apply(&self, camera: &mut Camera) {
+        camera.eye = self.eye;
+        camera.center = self.center;
+        camera.fov = self.fov;
+        camera.focal_distance = self.focal_distance;
+        camera.up = Vector3::new(0.0, 1.0, 0.0);
+        camera.update_basis();
+    }
+}
+
+pub struct CameraPath {
+    pub keys: Vec<Keyframe>, // sorted by time
+    pub interpolation: Interpolation,
+    pub looping: bool, // wrap around: last key blends back into the first
+    pub loop_duration: Option<f32>, // length of one loop; None = keys are evenly spaced
+}
+
+impl CameraPath {
+    pub fn new(mut keys: Vec<Keyframe>, interpolation: Interpolation, looping: bool) -> Self {
+        keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+        CameraPath { keys, interpolation, looping, loop_duration: None }
+    }
+
+    /// Full 360° orbit around `center` at `radius` and `height` above it, in `duration` seconds.
+    pub fn turntable(center: Vector3, radius: f32, height: f32, duration: f32, fov: f32) -> Self {
+        let segments = 16;
+        let keys = (0..segments)
+            .map(|i| {
+                let a = 2.0 * PI * i as f32 / segments as f32;
+                let eye = center + Vector3::new(radius * a.cos(), height, radius * a.sin());
+                Keyframe::new(duration * i as f32 / segments as f32, eye, center, fov)
+            })
+            .collect();
+        let mut path = CameraPath::new(keys, Interpolation::CatmullRom, true);
+        path.loop_duration = Some(duration);
+        path
+    }
+
+    pub fn duration(&self) -> f32 {
+        match (self.keys.first(), self.keys.last()) {
+            (Some(first), Some(last)) => last.time - first.time,
+            _ => 0.0,
+        }
+    }
+
+    pub fn sample(&self, t: f32) -> Option<CameraPose> {
+        let n = self.keys.len();
+        let first = self.keys.first()?;
+        if n == 1 {
+            return Some(pose_of(first));
+        }
+
+        // Find the segment [i, i+1] containing t
+        let (i, u) = self.locate(t);
+        let k = |j: isize| -> &Keyframe {
+            if self.looping {
+                &self.keys[j.rem_euclid(n as isize) as usize]
+            } else {
+                &self.keys[j.clamp(0, n as isize - 1) as usize]
+            }
+        };
+        let (k0, k1, k2, k3) = (k(i - 1), k(i), k(i + 1), k(i + 2));
+        let u = k1.easing.apply(u);
+
+        let (eye, center) = match self.interpolation {
+            Interpolation::Linear => (k1.eye.lerp(k2.eye, u), k1.center.lerp(k2.center, u)),
+            Interpolation::CatmullRom => (
+                catmull_rom(k0.eye, k1.eye, k2.eye, k3.eye, u),
+                catmull_rom(k0.center, k1.center, k2.center, k3.center, u),
+            ),
+            Interpolation::Bezier => {
+                let eye_t1 = k1.eye_tangent.unwrap_or((k2.eye - k0.eye) * 0.5);
+                let eye_t2 = k2.eye_tangent.unwrap_or((k3.eye - k1.eye) * 0.5);
+                let c_t1 = k1.center_tangent.unwrap_or((k2.center - k0.center) * 0.5);
+                let c_t2 = k2.center_tangent.unwrap_or((k3.center - k1.center) * 0.5);
+                (
+                    bezier(k1.eye, k1.eye + eye_t1 / 3.0, k2.eye - eye_t2 / 3.0, k2.eye, u),
+                    bezier(k1.center, k1.center + c_t1 / 3.0, k2.center - c_t2 / 3.0, k2.center, u),
+                )
+            }
+        };
+
+        Some(CameraPose {
+            eye,
+            center,
+            fov: k1.fov + (k2.fov - k1.fov) * u,
+            focal_distance: k1.focal_distance + (k2.focal_distance - k1.focal_distance) * u,
+        })
+    }
+
+    // Segment index and local parameter in [0,1] for time t
+    fn locate(&self, t: f32) -> (isize, f32) {
+        let n = self.keys.len();
+        let start = self.keys[0].time;
+        let end = self.keys[n - 1].time;
+
+        if self.looping {
+            // the closing segment (last → first) lasts as long as the average segment
+            let period = self.period();
+            let t = start + (t - start).rem_euclid(period);
+            if t >= end {
+                let closing = period - (end - start);
+                return ((n - 1) as isize, ((t - end) / closing.max(1e-6)).clamp(0.0, 1.0));
+            }
+            return self.segment_at(t);
+        }
+
+        if t <= start {
+            return (0, 0.0);
+        }
+        if t >= end {
+            return ((n - 2) as isize, 1.0);
+        }
+        self.segment_at(t)
+    }
+
+    fn segment_at(&self, t: f32) -> (isize, f32) {
+        for i in 0..self.keys.len() - 1 {
+            let (a, b) = (self.keys[i].time, self.keys[i + 1].time);
+            if t >= a && t < b {
+                return (i as isize, (t - a) / (b - a).max(1e-6));
+            }
+        }
+        ((self.keys.len() - 2) as isize, 1.0)
+    }
+
+    /// Length of one loop, including the closing segment back to the first key.
+    pub fn period(&self) -> f32 {
+        if let Some(d) = self.loop_duration {
+            return d;
+        }
+        let n = self.keys.len().max(2);
+        self.duration() * n as f32 / (n - 1) as f32
+    }
+}
+
+fn pose_of(k: &Keyframe) -> CameraPose {
+    CameraPose { eye: k.eye, center: k.center, fov: k.fov, focal_distance: k.focal_distance }
+}
+
+fn catmull_rom(p0: Vector3, p1: Vector3, p2: Vector3, p3: Vector3, u: f32) -> Vector3 {
+    let u2 = u * u;
+    let u3 = u2 * u;
+    (p1 * 2.0
+        + (p2 - p0) * u
+        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * u2
+        + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * u3)
+        * 0.5
+}
+
+fn bezier(p0: Vector3, c0: Vector3, c1: Vector3, p1: Vector3, u: f32) -> Vector3 {
+    let v = 1.0 - u;
+    p0 * (v * v * v) + c0 * (3.0 * v * v * u) + c1 * (3.0 * v * u * u) + p1 * (u * u * u)
+}
+
+// -------- Reloj de tiempo fijo para render offline --------
+pub struct FrameClock {
+    pub fps: f32,
+    pub frame_count: usize,
+}
+
+impl FrameClock {
+    pub fn new(fps: f32, frame_count: usize) -> Self {
+        FrameClock { fps, frame_count }
+    }
+
+    /// Enough frames to cover `path` once at `fps`.
+    pub fn for_path(path: &CameraPath, fps: f32) -> Self {
+        let length = if path.looping { path.period() } else { path.duration() };
+        FrameClock::new(fps, (length * fps).round().max(1.0) as usize)
+    }
+
+    pub fn dt(&self) -> f32 {
+        1.0 / self.fps
+    }
+
+    /// (frame index, time in seconds) for every frame of the sequence
+    pub fn frames(&self) -> impl Iterator<Item = (usize, f32)> + '_ {
+        (0..self.frame_count).map(move |i| (i, i as f32 / self.fps))
+    }
+}
@@ -0,0 +1,284 @@
+// aov.rs
+use std::io::{self, Write};
+
+use raylib::prelude::*;
+
+use crate::{
+    color::linear_to_srgb,
+    image_io::{Channel, ExrPixelType, rgb_channels, write_exr, write_pfm},
+    ray_intersect::ObjectId,
+};
+
+// Pasadas extra que `render` puede escribir además del color final
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Aov {
+    Depth,
+    Normal,
+    Albedo,
+    ObjectId,
+    MaterialId,
+    Uv,
+    Direct,   // local lighting at the first hit (ambient + diffuse + specular)
+    Indirect, // reflection + refraction seen through the first hit
+    Emission,
+    ShadowMask, // fraction of the light blocked at the first hit (1 = fully shadowed)
+}
+
+impl Aov {
+    pub const ALL: [Aov; 10] = [
+        Aov::Depth,
+        Aov::Normal,
+        Aov::Albedo,
+        Aov::ObjectId,
+        Aov::MaterialId,
+        Aov::Uv,
+        Aov::Direct,
+        Aov::Indirect,
+        Aov::Emission,
+        Aov::ShadowMask,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Aov::Depth => "depth",
+            Aov::Normal => "normal",
+            Aov::Albedo => "albedo",
+            Aov::ObjectId => "object_id",
+            Aov::MaterialId => "material_id",
+            Aov::Uv => "uv",
+            Aov::Direct => "direct",
+            Aov::Indirect => "indirect",
+            Aov::Emission => "emission",
+            Aov::ShadowMask => "shadow",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Aov> {
+        Aov::ALL.iter().copied().find(|a| a.name() == name)
+    }
+
+    /// Channel names of the raw float layer.
+    pub fn channels(self) -> &'static [&'static str] {
+        match self {
+            Aov::Depth => &["Z"],
+            Aov::Normal => &["X", "Y", "Z"],
+            Aov::ObjectId | Aov::MaterialId => &["id"],
+            Aov::Uv => &["U", "V"],
+            Aov::ShadowMask => &["A"],
+            Aov::Albedo | Aov::Direct | Aov::Indirect | Aov::Emission => &["R", "G", "B"],
+        }
+    }
+}
+
+// -------- Valores por pixel (primer hit del rayo pinhole) --------
+#[derive(Clone, Copy, Debug)]
+pub struct AovSample {
+    pub depth: f32,        // hit distance along the primary ray (INFINITY = sky)
+    pub position: Vector3, // world-space hit point
+    pub normal: Vector3,   // world-space normal
+    pub albedo: Vector3,   // linear base color
+    pub obj_id: Option<ObjectId>,
+    pub material_id: u32,
+    pub uv: Vector2,
+    pub direct: Vector3,   // linear
+    pub indirect: Vector3, // linear
+    pub emission: Vector3, // linear, already scaled by emission_strength
+    pub shadow: f32,
+}
+
+impl AovSample {
+    pub fn sky() -> Self {
+        AovSample {
+            depth: f32::INFINITY,
+            position: Vector3::zero(),
+            normal: Vector3::zero(),
+            albedo: Vector3::new(1.0, 1.0, 1.0),
+            obj_id: None,
+            material_id: 0,
+            uv: Vector2::zero(),
+            direct: Vector3::zero(),
+            indirect: Vector3::zero(),
+            emission: Vector3::zero(),
+            shadow: 0.0,
+        }
+    }
+
+    pub fn is_sky(&self) -> bool {
+        !self.depth.is_finite()
+    }
+
+    /// Raw float channels of one pass (only the first `aov.channels().len()` are meaningful).
+    pub fn values(&self, aov: Aov) -> [f32; 3] {
+        let v3 = |v: Vector3| [v.x, v.y, v.z];
+        match aov {
+            Aov::Depth => [self.depth, 0.0, 0.0],
+            Aov::Normal => v3(self.normal),
+            Aov::Albedo => v3(self.albedo),
+            Aov::ObjectId => [self.obj_id.map_or(-1.0, |id| id.0 as f32), 0.0, 0.0],
+            Aov::MaterialId => [self.material_id as f32, 0.0, 0.0],
+            Aov::Uv => [self.uv.x, self.uv.y, 0.0],
+            Aov::Direct => v3(self.direct),
+            Aov::Indirect => v3(self.indirect),
+            Aov::Emission => v3(self.emission),
+            Aov::ShadowMask => [self.shadow, 0.0, 0.0],
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AovBuffer {
+    pub width: usize,
+    pub height: usize,
+    pub samples: Vec<AovSample>, // row-major, same layout as the framebuffer
+}
+
+impl AovBuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        AovBuffer { width, height, samples: vec![AovSample::sky(); width * height] }
+    }
+
+    /// Clears to sky, reallocating only if the size changed.
+    pub fn reset(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.samples.clear();
+        self.samples.resize(width * height, AovSample::sky());
+    }
+
+    /// Raw float layer, channels interleaved per pixel.
+    pub fn layer(&self, aov: Aov) -> Vec<f32> {
+        let n = aov.channels().len();
+        self.samples.iter().flat_map(|s| s.values(aov)[..n].to_vec()).collect()
+    }
+
+    fn max_depth(&self) -> f32 {
+        self.samples
+            .iter()
+            .filter(|s| !s.is_sky())
+            .map(|s| s.depth)
+            .fold(0.0, f32::max)
+    }
+
+    /// Displayable version of a pass, every channel mapped to [0,1].
+    pub fn preview(&self, aov: Aov) -> Vec<Vector3> {
+        let max_depth = self.max_depth().max(1e-3);
+        self.samples
+            .iter()
+            .map(|s| {
+                if s.is_sky() && aov != Aov::Emission {
+                    return Vector3::zero();
+                }
+                match aov {
+                    Aov::Depth => {
+                        let d = 1.0 - s.depth / max_depth; // near = bright
+                        Vector3::new(d, d, d)
+                    }
+                    Aov::Normal => s.normal * 0.5 + Vector3::new(0.5, 0.5, 0.5),
+                    Aov::ObjectId => id_color(s.obj_id.map_or(0, |id| id.0.saturating_add(1))),
+                    Aov::MaterialId => id_color(s.material_id),
+                    Aov::Uv => Vector3::new(s.uv.x.fract(), s.uv.y.fract(), 0.0),
+                    Aov::ShadowMask => Vector3::new(s.shadow, s.shadow, s.shadow),
+                    Aov::Albedo => s.albedo,
+                    Aov::Direct => s.direct,
+                    Aov::Indirect => s.indirect,
+                    Aov::Emission => s.emission,
+                }
+                .clamp(0.0..1.0)
+            })
+            .collect()
+    }
+
+    /// Writes one pass as an 8-bit image (PNG/BMP/... by extension).
+    pub fn export_image(&self, aov: Aov, path: &str) {
+        let mut image = Image::gen_image_color(self.width as i32, self.height as i32, Color::BLACK);
+        for (i, c) in self.preview(aov).iter().enumerate() {
+            let (x, y) = ((i % self.width) as i32, (i / self.width) as i32);
+            image.draw_pixel(x, y, linear_to_srgb(c.x, c.y, c.z));
+        }
+        image.export_image(path);
+    }
+
+    /// Writes every pass as `dir/<prefix>_<pass>.png`; returns the written paths.
+    pub fn export_all(&self, dir: &str, prefix: &str) -> io::Result<Vec<String>> {
+        std::fs::create_dir_all(dir)?;
+        let mut written = Vec::new();
+        for aov in Aov::ALL {
+            let path = format!("{}/{}_{}.png", dir, prefix, aov.name());
+            self.export_image(aov, &path);
+            written.push(path);
+        }
+        Ok(written)
+    }
+
+    /// Writes the given passes as layers of one multi-channel 16-bit PAM (netpbm P7) file.
+    /// Channel order is listed in the header comments; values are the `preview` encoding.
+    pub fn export_layers(&self, aovs: &[Aov], path: &str) -> io::Result<()> {
+        let previews: Vec<Vec<Vector3>> = aovs.iter().map(|a| self.preview(*a)).collect();
+        let depth: usize = aovs.iter().map(|a| a.channels().len()).sum();
+
+        let mut out = io::BufWriter::new(std::fs::File::create(path)?);
+        writeln!(out, "P7")?;
+        for aov in aovs {
+            let names: Vec<String> = aov.channels().iter().map(|c| format!("{}.{}", aov.name(), c)).collect();
+            writeln!(out, "# layer {}", names.join(" "))?;
+        }
+        writeln!(out, "# depth.Z = 1 - distance / {}", self.max_depth())?;
+        writeln!(out, "WIDTH {}", self.width)?;
+        writeln!(out, "HEIGHT {}", self.height)?;
+        writeln!(out, "DEPTH {}", depth)?;
+        writeln!(out, "MAXVAL 65535")?;
+        writeln!(out, "TUPLTYPE AOV")?;
+        writeln!(out, "ENDHDR")?;
+
+        for i in 0..self.samples.len() {
+            for (aov, preview) in aovs.iter().zip(&previews) {
+                let c = preview[i];
+                for v in &[c.x, c.y, c.z][..aov.channels().len()] {
+                    out.write_all(&((v * 65535.0).round() as u16).to_be_bytes())?;
+                }
+            }
+        }
+        out.flush()
+    }
+
+    /// Raw float values of the given passes (plus the beauty as R/G/B) as layers of one OpenEXR file.
+    pub fn export_exr(&self, aovs: &[Aov], beauty: Option<&[Vector3]>, path: &str, pixel_type: ExrPixelType) -> io::Result<()> {
+        let mut channels = beauty.map(|b| rgb_channels("", b)).unwrap_or_default();
+        for &aov in aovs {
+            let values = self.layer(aov);
+            let n = aov.channels().len();
+            for (k, name) in aov.channels().iter().enumerate() {
+                let data = values.iter().skip(k).step_by(n).copied().collect();
+                channels.push(Channel::new(&format!("{}.{}", aov.name(), name), data));
+            }
+        }
+        write_exr(path, self.width, self.height, &channels, pixel_type)
+    }
+
+    /// Raw float values of one pass as PFM; UV gets a zero third channel since PFM has no 2-channel form.
+    pub fn export_pfm(&self, aov: Aov, path: &str) -> io::Result<()> {
+        match aov.channels().len() {
+            2 => {
+                let values: Vec<f32> = self.samples.iter().flat_map(|s| s.values(aov)).collect();
+                write_pfm(path, self.width, self.height, 3, &values)
+            }
+            n => write_pfm(path, self.width, self.height, n, &self.layer(aov)),
+        }
+    }
+}
+
+// Stable, well separated color for an id (0 = black)
+fn id_color(id: u32) -> Vector3 {
+    if id == 0 {
+        return Vector3::zero();
+    }
+    let mut h = id.wrapping_mul(0x9e37_79b9);
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    Vector3::new(
+        (h & 0xff) as f32 / 255.0,
+        ((h >> 8) & 0xff) as f32 / 255.0,
+        ((h >> 16) & 0xff) as f32 / 255.0,
+    )
+}
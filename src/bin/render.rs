@@ -0,0 +1,124 @@
+// render: headless CLI, no window or GPU needed
+//
+//   render [--size W H] --turntable [frames] [out_dir]   360° turntable as a PNG sequence
+//   render [--size W H] --aovs [out_dir]                 one frame with every AOV pass
+//   render [--size W H] [file.png]                       one frame (default frame.png)
+//
+// `--integrator NAME` picks the shading (whitted, path, ao, normals, uv, albedo, heatmap),
+// `--ao MODE` the ambient occlusion (off, voxel, hemisphere); `--voxel-light` lights lamp blocks
+// with flood-filled light levels instead of shadow rays; `--blocks FILE` loads blocks saved in the viewer (F5).
+// `--tiles ORDER` picks the order tiles are traced in (spiral, hilbert, scanline); single frames
+// print their progress and ETA.
+use std::io::Write;
+
+use raylib::prelude::*;
+
+use raytracing::{
+    AoMode, AovBuffer, Camera, CameraPath, FrameClock, Framebuffer, INTEGRATORS, Progress, Renderer, Scene, SpriteSystem,
+    TextureManager, TileOrder, diorama_emitters, export_aovs, integrator_by_name, load_blocks,
+};
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let (width, height) = match args.iter().position(|a| a == "--size") {
+        Some(i) => (
+            args.get(i + 1).and_then(|w| w.parse().ok()).unwrap_or(500),
+            args.get(i + 2).and_then(|h| h.parse().ok()).unwrap_or(250),
+        ),
+        None => (500, 250),
+    };
+
+    let mut framebuffer = Framebuffer::new(width, height, Color::BLACK);
+    framebuffer.set_background_color(Color::new(4, 12, 36, 255));
+    let mut scene = Scene::diorama(TextureManager::load());
+    let mut camera = Camera::new(
+        Vector3::new(0.0, 0.0, 20.0),
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
+    );
+    if let Some(i) = args.iter().position(|a| a == "--ao") {
+        let name = args.get(i + 1).map(|s| s.as_str()).unwrap_or("");
+        let Some(mode) = AoMode::from_name(name) else {
+            eprintln!("unknown ambient occlusion '{}' (one of: off, voxel, hemisphere)", name);
+            std::process::exit(2);
+        };
+        scene.ao = mode;
+    }
+    scene.voxel_lighting = args.iter().any(|a| a == "--voxel-light");
+    if let Some(i) = args.iter().position(|a| a == "--blocks") {
+        let file = args.get(i + 1).map(|s| s.as_str()).unwrap_or("blocks.txt");
+        load_blocks(&mut scene, file).expect("failed to load blocks");
+    }
+    scene.sprites = SpriteSystem::new(diorama_emitters());
+    scene.prewarm_particles(2.0);
+    let mut renderer = Renderer::new();
+    if let Some(i) = args.iter().position(|a| a == "--integrator") {
+        let name = args.get(i + 1).map(|s| s.as_str()).unwrap_or("");
+        let Some(integrator) = integrator_by_name(name) else {
+            eprintln!("unknown integrator '{}' (one of: {})", name, INTEGRATORS.join(", "));
+            std::process::exit(2);
+        };
+        renderer.integrator = integrator;
+    }
+    if let Some(i) = args.iter().position(|a| a == "--tiles") {
+        let name = args.get(i + 1).map(|s| s.as_str()).unwrap_or("");
+        let Some(order) = TileOrder::from_name(name) else {
+            eprintln!("unknown tile order '{}' (one of: spiral, hilbert, scanline)", name);
+            std::process::exit(2);
+        };
+        renderer.tile_order = order;
+    }
+
+    if let Some(i) = args.iter().position(|a| a == "--turntable") {
+        let fps = 30.0;
+        let frames = args.get(i + 1).and_then(|f| f.parse().ok()).unwrap_or(360);
+        let out_dir = args.get(i + 2).map(|s| s.as_str()).unwrap_or("frames");
+        let path = CameraPath::turntable(Vector3::zero(), 20.0, 6.0, frames as f32 / fps, camera.fov);
+        let clock = FrameClock::new(fps, frames);
+        camera.shutter = 0.5 / fps; // 180° shutter, like a film camera
+        renderer
+            .render_sequence(&mut framebuffer, &mut scene, &mut camera, &path, &clock, out_dir, |file, progress| {
+                // ETA for the whole sequence, from the average time per frame so far
+                let left = progress.eta().unwrap_or_default();
+                println!("frame {}/{} -> {} ({:.1}s left)", progress.tiles_done, progress.tiles_total, file, left.as_secs_f32());
+            })
+            .expect("failed to write frame sequence");
+        return;
+    }
+
+    scene.update(0.0, &camera);
+
+    // `--aovs [out_dir]` renders one frame with every pass
+    if let Some(i) = args.iter().position(|a| a == "--aovs") {
+        let out_dir = args.get(i + 1).map(|s| s.as_str()).unwrap_or("aovs");
+        let mut passes = AovBuffer::new(0, 0);
+        renderer.render_progressive(&mut framebuffer, &scene, &camera, Some(&mut passes), report_progress());
+        for file in export_aovs(&framebuffer, &passes, out_dir, "frame").expect("failed to write AOVs") {
+            println!("{}", file);
+        }
+        return;
+    }
+
+    let file = args.iter().skip(1).find(|a| a.ends_with(".png")).map(|s| s.as_str()).unwrap_or("frame.png");
+    renderer.render_progressive(&mut framebuffer, &scene, &camera, None, report_progress());
+    framebuffer.render_to_file(file);
+    println!("{}", file);
+}
+
+// Progress callback: `  42%  1.3s left` on one stderr line, rewritten each whole percent
+fn report_progress() -> impl FnMut(&Framebuffer, Progress) -> bool {
+    let mut shown = None;
+    move |_, progress| {
+        let percent = (progress.fraction() * 100.0) as u32;
+        if shown != Some(percent) {
+            shown = Some(percent);
+            let left = progress.eta().map_or(String::new(), |eta| format!("  {:.1}s left", eta.as_secs_f32()));
+            eprint!("\r{:4}%{:<16}", percent, left);
+            if progress.is_done() {
+                eprintln!("  ({:.1}s)", progress.elapsed.as_secs_f32());
+            }
+            let _ = std::io::stderr().flush();
+        }
+        true
+    }
+}
@@ -0,0 +1,39 @@
+// hotbar.rs: block slots along the bottom of the window, and the crosshair in fly mode
+use raylib::prelude::*;
+
+use raytracing::{BlockType, Hotbar, TextureManager};
+
+const SLOT: i32 = 28; // px
+const GAP: i32 = 4;
+
+pub fn draw_hotbar(d: &mut RaylibDrawHandle, hotbar: &Hotbar, texmgr: &TextureManager, crosshair: bool) {
+    let (w, h) = (d.get_screen_width(), d.get_screen_height());
+    let n = BlockType::HOTBAR.len() as i32;
+    let x0 = (w - (n * SLOT + (n - 1) * GAP)) / 2;
+    let y = h - SLOT - 6;
+
+    for (i, block) in BlockType::HOTBAR.iter().enumerate() {
+        let x = x0 + i as i32 * (SLOT + GAP);
+        d.draw_rectangle(x - 2, y - 2, SLOT + 4, SLOT + 4, Color::new(0, 0, 0, 140));
+        if let Some(tex) = texmgr.get_texture(block.texture()) {
+            // first square of the texture (animated ones are stacked vertically)
+            let side = tex.width.min(tex.height) as f32;
+            let src = Rectangle::new(0.0, 0.0, side, side);
+            let dst = Rectangle::new(x as f32, y as f32, SLOT as f32, SLOT as f32);
+            d.draw_texture_pro(tex, src, dst, Vector2::zero(), 0.0, Color::WHITE);
+        }
+        if i == hotbar.selected {
+            d.draw_rectangle_lines(x - 2, y - 2, SLOT + 4, SLOT + 4, Color::WHITE);
+        }
+    }
+
+    let name = hotbar.block().name();
+    let tw = d.measure_text(name, 10);
+    d.draw_text(name, (w - tw) / 2, y - 14, 10, Color::WHITE);
+
+    if crosshair {
+        let (cx, cy) = (w / 2, h / 2);
+        d.draw_line(cx - 5, cy, cx + 5, cy, Color::WHITE);
+        d.draw_line(cx, cy - 5, cx, cy + 5, Color::WHITE);
+    }
+}
@@ -0,0 +1,138 @@
+// hud.rs: performance and debug overlay (F3), drawn over the presented frame
+use std::time::Duration;
+
+use raylib::prelude::*;
+
+use raytracing::{Camera, CameraMode, Framebuffer, Pick, Progress, Renderer, Scene};
+
+const FONT: i32 = 10;
+const LINE: i32 = 12;
+const SMOOTHING: f32 = 0.1; // weight of the newest frame in the averages
+
+/// Wall-clock time of each phase of a viewer frame.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameTimes {
+    pub trace: Duration,   // Renderer::render (tracing + denoising)
+    pub sprites: Duration, // Scene::update (sky, particles, lights) + compositing sprites
+    pub present: Duration, // upscaling + texture upload + drawing
+}
+
+pub struct Hud {
+    pub visible: bool,
+    // running averages in milliseconds, so the numbers can be read
+    frame_ms: f32,
+    trace_ms: f32,
+    sprites_ms: f32,
+    present_ms: f32,
+    rays_per_s: f64,
+    rays: u64,
+}
+
+impl Default for Hud {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hud {
+    pub fn new() -> Self {
+        Hud { visible: false, frame_ms: 0.0, trace_ms: 0.0, sprites_ms: 0.0, present_ms: 0.0, rays_per_s: 0.0, rays: 0 }
+    }
+
+    pub fn record(&mut self, dt: f32, times: FrameTimes, renderer: &Renderer) {
+        let avg = |old: f32, new: f32| if old == 0.0 { new } else { old + (new - old) * SMOOTHING };
+        let ms = |d: Duration| d.as_secs_f32() * 1000.0;
+        self.frame_ms = avg(self.frame_ms, dt * 1000.0);
+        self.trace_ms = avg(self.trace_ms, ms(times.trace));
+        self.sprites_ms = avg(self.sprites_ms, ms(times.sprites));
+        self.present_ms = avg(self.present_ms, ms(times.present));
+        let rps = renderer.stats.rays_per_second();
+        self.rays_per_s = if self.rays_per_s == 0.0 { rps } else { self.rays_per_s + (rps - self.rays_per_s) * SMOOTHING as f64 };
+        self.rays = renderer.stats.rays;
+    }
+
+    /// Text of the overlay (empty while hidden). Built before tracing, so streamed tiles
+    /// can be presented with it while the renderer is busy.
+    pub fn lines(&self, window: &RaylibHandle, scene: &Scene, camera: &Camera, renderer: &Renderer, traced: &Framebuffer, hovered: Option<&Pick>) -> Vec<String> {
+        if !self.visible {
+            return Vec::new();
+        }
+        let (hours, minutes) = scene.sky.clock();
+        let eye = camera.eye;
+        let mut lines = vec![
+            format!("{} fps  {:.1} ms", window.get_fps(), self.frame_ms),
+            format!("trace {:.1}  sprites {:.1}  present {:.1} ms", self.trace_ms, self.sprites_ms, self.present_ms),
+            format!(
+                "{:.2} Mrays/s  {} k rays/frame  reused {:.0}%",
+                self.rays_per_s / 1e6,
+                self.rays / 1000,
+                renderer.stats.reuse_ratio() * 100.0
+            ),
+            format!(
+                "traced {}x{} ({:.0}% of {}x{})",
+                traced.width,
+                traced.height,
+                100.0 * traced.width as f32 / window.get_screen_width().max(1) as f32,
+                window.get_screen_width(),
+                window.get_screen_height()
+            ),
+            format!(
+                "{} objects  {} triangles  {} lights  {} particles",
+                scene.len(),
+                scene.triangle_count(),
+                scene.lights().len(),
+                scene.sprites.sprites.len()
+            ),
+            format!("time {:02}:{:02}", hours, minutes),
+            format!(
+                "camera {:.2} {:.2} {:.2}  {}",
+                eye.x,
+                eye.y,
+                eye.z,
+                if camera.mode == CameraMode::Fly { "fly" } else { "orbit" }
+            ),
+            format!(
+                "{}  ao {}  denoise {:?}  tiles {}",
+                renderer.integrator.name(),
+                scene.ao.name(),
+                renderer.denoiser.mode,
+                renderer.tile_order.name()
+            ),
+        ];
+        match hovered {
+            Some(pick) => {
+                let m = pick.material;
+                let kind = scene.get(pick.id).map_or("?", |o| o.kind());
+                lines.push(format!("hover #{} {} ({})  {:.1} away", pick.id.0, kind, pick.face.name(), pick.distance));
+                lines.push(format!(
+                    "  texture {}  albedo {:.2}  spec {:.2}  refl {:.2}",
+                    m.texture.map_or("-".to_string(), |c| format!("'{}'", c)),
+                    m.albedo,
+                    m.specular_strength,
+                    m.reflectivity
+                ));
+                lines.push(format!("  transp {:.2}  ior {:.2}  emission {:.1}", m.transparency, m.ior, m.emission_strength));
+            }
+            None => lines.push("hover -".to_string()),
+        }
+        lines
+    }
+
+    pub fn draw(&self, d: &mut RaylibDrawHandle, lines: &[String]) {
+        if lines.is_empty() {
+            return;
+        }
+        let width = lines.iter().map(|l| d.measure_text(l, FONT)).max().unwrap_or(0);
+        d.draw_rectangle(2, 2, width + 8, lines.len() as i32 * LINE + 6, Color::new(0, 0, 0, 150));
+        for (i, line) in lines.iter().enumerate() {
+            d.draw_text(line, 6, 6 + i as i32 * LINE, FONT, Color::WHITE);
+        }
+    }
+}
+
+/// Thin bar along the top of the window while a long frame is being streamed tile by tile.
+pub fn draw_progress(d: &mut RaylibDrawHandle, progress: &Progress) {
+    let w = d.get_screen_width();
+    d.draw_rectangle(0, 0, w, 3, Color::new(0, 0, 0, 150));
+    d.draw_rectangle(0, 0, (w as f32 * progress.fraction()) as i32, 3, Color::WHITE);
+}
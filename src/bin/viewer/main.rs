@@ -0,0 +1,327 @@
+// viewer: raylib window, music and keyboard on top of the `raytracing` library
+use raylib::prelude::*;
+use std::f32::consts::PI;
+use std::time::{Duration, Instant};
+
+mod hotbar;
+mod hud;
+
+use raytracing::{
+    AovBuffer, Camera, CameraMode, CameraPath, DynamicResolution, Framebuffer, Hotbar, Motion, Progress, Renderer, Scene,
+    SpriteSystem, TextureManager, break_block, diorama_emitters, export_aovs, load_blocks, next_integrator, place_block,
+    render_sprites, save_blocks, upscale, upscale_sequential,
+};
+
+// Long frames are presented this often while their tiles come in
+const STREAM_INTERVAL: Duration = Duration::from_millis(50);
+
+fn main() {
+    let window_width = 500;
+    let window_height = 250;
+
+    let (mut window, raylib_thread) = raylib::init()
+        .size(window_width, window_height)
+        .title("Raytracer (Textured)")
+        .resizable()
+        .log_level(TraceLogLevel::LOG_WARNING)
+        .build();
+
+    let audio = raylib::audio::RaylibAudio::init_audio_device().expect("Failed to initialize audio device");
+    let music = audio
+        .new_music("assets/calm1.ogg")
+        .expect("failed to load music");
+    music.play_stream();
+
+    // `framebuffer` is traced at the internal resolution and stretched onto `display` (window size)
+    let mut framebuffer = Framebuffer::new(window_width as u32, window_height as u32, Color::BLACK);
+    framebuffer.set_background_color(Color::new(4, 12, 36, 255));
+    let mut display = Framebuffer::new(window_width as u32, window_height as u32, Color::BLACK);
+    // H: full resolution → dynamic + bilinear → dynamic + edge-aware upscaling
+    let mut resolution = DynamicResolution::new();
+
+    // Escena: bloques, lámparas, portal y el modelo OBJ (texturas cargadas una vez)
+    let mut scene = Scene::diorama(TextureManager::new(&mut window, &raylib_thread));
+    
+    let mut camera = Camera::new(
+        Vector3::new(0.0, 0.0, 20.0),
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
+    );
+    let rotation_speed = PI / 100.0;
+    let zoom_speed = 1.0;
+
+    // Portal swirl, smoke over both lamps, falling leaves and (Y) rain
+    scene.sprites = SpriteSystem::new(diorama_emitters());
+    scene.prewarm_particles(2.0);
+
+    let mut renderer = Renderer::new();
+    // F4: reuse last frame's shading where the same surface is still visible
+    renderer.temporal.enabled = true;
+
+    // 360° turntable around the diorama: K plays it in the window
+    // (the `render` binary writes it to disk)
+    let turntable = CameraPath::turntable(Vector3::zero(), 20.0, 6.0, 12.0, camera.fov);
+    let mut playback: Option<f32> = None;
+    let mut prev_eye = camera.eye;
+    let mut prev_center = camera.center;
+    let mut capture_aovs = false;
+    // Blocks: left click breaks, right click places the hotbar block (1-5 / wheel), F5 saves, F9 loads
+    let mut hotbar = Hotbar::new();
+    let blocks_file = "blocks.txt";
+    // F3: FPS, frame phases, rays/s, counts, clock, camera and the hovered object
+    let mut hud = hud::Hud::new();
+    let mut present_time = Duration::ZERO; // measured one frame late
+
+    while !window.window_should_close() {
+        music.update_stream();
+        let dt = window.get_frame_time();
+        if window.is_window_resized() {
+            display.resize(window.get_screen_width().max(1) as u32, window.get_screen_height().max(1) as u32);
+        }
+        if window.is_key_pressed(KeyboardKey::KEY_H) {
+            resolution.cycle();
+            println!("resolution: {}", resolution.name());
+        }
+
+        // C toggles orbit / fly, G toggles collision while flying
+        if window.is_key_pressed(KeyboardKey::KEY_C) {
+            camera.toggle_mode();
+            match camera.mode {
+                CameraMode::Fly => window.disable_cursor(),
+                CameraMode::Orbit => window.enable_cursor(),
+            }
+        }
+        if window.is_key_pressed(KeyboardKey::KEY_G)  { camera.collision = !camera.collision; }
+
+        match camera.mode {
+            CameraMode::Orbit => {
+                if window.is_key_down(KeyboardKey::KEY_LEFT)  { camera.orbit( rotation_speed, 0.0); }
+                if window.is_key_down(KeyboardKey::KEY_RIGHT) { camera.orbit(-rotation_speed, 0.0); }
+                if window.is_key_down(KeyboardKey::KEY_UP)    { camera.orbit(0.0, -rotation_speed); }
+                if window.is_key_down(KeyboardKey::KEY_DOWN)  { camera.orbit(0.0,  rotation_speed); }
+                if window.is_key_down(KeyboardKey::KEY_R)     { camera.zoom(zoom_speed); }
+                if window.is_key_down(KeyboardKey::KEY_F)     { camera.zoom(-zoom_speed); }
+            }
+            CameraMode::Fly => {
+                // Mouse look (arrows still work for keyboard-only setups)
+                let md = window.get_mouse_delta();
+                camera.look(md.x * camera.look_sensitivity, md.y * camera.look_sensitivity);
+                if window.is_key_down(KeyboardKey::KEY_LEFT)  { camera.look(-rotation_speed, 0.0); }
+                if window.is_key_down(KeyboardKey::KEY_RIGHT) { camera.look( rotation_speed, 0.0); }
+                if window.is_key_down(KeyboardKey::KEY_UP)    { camera.look(0.0, -rotation_speed); }
+                if window.is_key_down(KeyboardKey::KEY_DOWN)  { camera.look(0.0,  rotation_speed); }
+
+                // WASD + Q/E (down/up), Shift = fast, Ctrl = slow
+                let mut wish = Vector3::zero();
+                if window.is_key_down(KeyboardKey::KEY_W) { wish += camera.forward; }
+                if window.is_key_down(KeyboardKey::KEY_S) { wish -= camera.forward; }
+                if window.is_key_down(KeyboardKey::KEY_D) { wish += camera.right; }
+                if window.is_key_down(KeyboardKey::KEY_A) { wish -= camera.right; }
+                if window.is_key_down(KeyboardKey::KEY_E) { wish += Vector3::new(0.0, 1.0, 0.0); }
+                if window.is_key_down(KeyboardKey::KEY_Q) { wish -= Vector3::new(0.0, 1.0, 0.0); }
+
+                let mut speed = camera.move_speed;
+                if window.is_key_down(KeyboardKey::KEY_LEFT_SHIFT)   { speed *= 4.0; }
+                if window.is_key_down(KeyboardKey::KEY_LEFT_CONTROL) { speed *= 0.25; }
+
+                if wish.length() > 0.0 {
+                    camera.fly(wish.normalized() * speed * dt, &scene);
+                }
+            }
+        }
+        // Depth of field: open/close the aperture, toggle autofocus
+        if window.is_key_down(KeyboardKey::KEY_O)     { camera.aperture = (camera.aperture + 0.005).min(0.5); }
+        if window.is_key_down(KeyboardKey::KEY_P)     { camera.aperture = (camera.aperture - 0.005).max(0.0); }
+        if window.is_key_pressed(KeyboardKey::KEY_T)  { camera.autofocus = !camera.autofocus; }
+        if camera.autofocus { camera.focus_on_center(&scene); }
+        // Field of view and projection
+        if window.is_key_down(KeyboardKey::KEY_Z)     { camera.fov = (camera.fov - 0.01).max(0.1); }
+        if window.is_key_down(KeyboardKey::KEY_X)     { camera.fov = (camera.fov + 0.01).min(camera.projection.max_fov()); }
+        if window.is_key_pressed(KeyboardKey::KEY_V)  {
+            camera.projection = camera.projection.next();
+            camera.fov = camera.fov.min(camera.projection.max_fov());
+        }
+        // Denoiser: N cycles off → À-Trous → SVGF
+        if window.is_key_pressed(KeyboardKey::KEY_N)  { renderer.denoiser.mode = renderer.denoiser.mode.next(); }
+        if window.is_key_pressed(KeyboardKey::KEY_M)  { capture_aovs = true; }
+        // U cycles the ambient occlusion: off → voxel corners → hemisphere
+        if window.is_key_pressed(KeyboardKey::KEY_U) {
+            scene.ao = scene.ao.next();
+            println!("ambient occlusion: {}", scene.ao.name());
+        }
+        // L: lamp blocks light the scene through flood-filled light levels instead of shadow rays
+        if window.is_key_pressed(KeyboardKey::KEY_L)  { scene.voxel_lighting = !scene.voxel_lighting; }
+        // I cycles the integrator: Whitted → path tracer → AO → normals → UV → albedo → heatmap
+        if window.is_key_pressed(KeyboardKey::KEY_I) {
+            renderer.integrator = next_integrator(renderer.integrator.as_ref());
+            println!("integrator: {}", renderer.integrator.name());
+        }
+        // J: particles as raytraced billboards (reflections, refraction, shadows) or screen-space sprites
+        if window.is_key_pressed(KeyboardKey::KEY_J)  { scene.raytraced_particles = !scene.raytraced_particles; }
+        if window.is_key_pressed(KeyboardKey::KEY_F3) { hud.visible = !hud.visible; }
+        if window.is_key_pressed(KeyboardKey::KEY_F4) {
+            renderer.temporal.enabled = !renderer.temporal.enabled;
+            println!("temporal reuse: {}", if renderer.temporal.enabled { "on" } else { "off" });
+        }
+        if window.is_key_pressed(KeyboardKey::KEY_Y) && let Some(rain) = scene.sprites.emitter_mut("rain") { rain.enabled = !rain.enabled; }
+        // Motion blur: B opens/closes the shutter (half a 60 fps frame)
+        if window.is_key_pressed(KeyboardKey::KEY_B)  { camera.shutter = if camera.shutter > 0.0 { 0.0 } else { 1.0 / 120.0 }; }
+        // Turntable playback
+        if window.is_key_pressed(KeyboardKey::KEY_K)  { playback = if playback.is_some() { None } else { Some(0.0) }; }
+        if let Some(t) = playback.as_mut() {
+            *t += dt;
+            if let Some(pose) = turntable.sample(*t) { pose.apply(&mut camera); }
+        }
+
+        // Block editing: the cursor picks in orbit mode, the screen centre (crosshair) in fly mode
+        for (slot, key) in [KeyboardKey::KEY_ONE, KeyboardKey::KEY_TWO, KeyboardKey::KEY_THREE, KeyboardKey::KEY_FOUR, KeyboardKey::KEY_FIVE].into_iter().enumerate() {
+            if window.is_key_pressed(key) { hotbar.select(slot); }
+        }
+        let wheel = window.get_mouse_wheel_move();
+        if wheel != 0.0 { hotbar.scroll(-wheel.signum() as i32); }
+        let (ndc_x, ndc_y) = match camera.mode {
+            CameraMode::Fly => (0.0, 0.0),
+            CameraMode::Orbit => {
+                let m = window.get_mouse_position();
+                (2.0 * m.x / window.get_screen_width() as f32 - 1.0, 1.0 - 2.0 * m.y / window.get_screen_height() as f32)
+            }
+        };
+        let aspect = display.width as f32 / display.height as f32;
+        let mut hovered = camera.pick(&scene, ndc_x, ndc_y, aspect);
+        if let Some(pick) = &hovered {
+            if window.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+                break_block(&mut scene, pick);
+                hovered = None;
+            } else if window.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT) {
+                place_block(&mut scene, pick, hotbar.block());
+                hovered = None;
+            }
+        }
+        if window.is_key_pressed(KeyboardKey::KEY_F5) {
+            match save_blocks(&scene, blocks_file) {
+                Ok(()) => println!("saved {}", blocks_file),
+                Err(e) => eprintln!("failed to save {}: {}", blocks_file, e),
+            }
+        }
+        if window.is_key_pressed(KeyboardKey::KEY_F9) {
+            match load_blocks(&mut scene, blocks_file) {
+                Ok(n) => println!("loaded {} blocks from {}", n, blocks_file),
+                Err(e) => eprintln!("failed to load {}: {}", blocks_file, e),
+            }
+        }
+
+        let moving = camera.eye != prev_eye || camera.center != prev_center;
+
+        // Camera blur comes from how far it moved since the last frame, stretched over the shutter
+        if camera.shutter > 0.0 && dt > 0.0 {
+            let scale = camera.shutter / dt;
+            let prev_eye = camera.eye + (prev_eye - camera.eye) * scale;
+            let prev_center = camera.center + (prev_center - camera.center) * scale;
+            camera.set_motion_from_previous(prev_eye, prev_center);
+        } else {
+            camera.eye_motion = Motion::Static;
+            camera.center_motion = Motion::Static;
+        }
+        prev_eye = camera.eye;
+        prev_center = camera.center;
+
+        // AOV captures are always traced at the window size
+        let (width, height) = if capture_aovs { (display.width, display.height) } else { resolution.internal_size(display.width, display.height) };
+        framebuffer.resize(width, height);
+        framebuffer.clear();
+
+        // sky, particles and lights advance together
+        let start = Instant::now();
+        scene.update(dt, &camera);
+        let mut sprites_time = start.elapsed();
+
+        let crosshair = camera.mode == CameraMode::Fly;
+        let hud_lines = hud.lines(&window, &scene, &camera, &renderer, &framebuffer, hovered.as_ref());
+        if capture_aovs {
+            // M: this frame also writes every AOV pass to aovs/
+            let mut passes = AovBuffer::new(0, 0);
+            renderer.render(&mut framebuffer, &scene, &camera, Some(&mut passes));
+            let written = export_aovs(&framebuffer, &passes, "aovs", "frame").expect("failed to write AOVs");
+            println!("{} files -> aovs/", written.len());
+            capture_aovs = false;
+        } else {
+            // Tiles come in from the centre; frames that take long are shown as they fill in
+            // and dropped as soon as there is new input, so the next one starts from the new view
+            let mut last_present = Instant::now();
+            let mut last_progress = None;
+            let finished = renderer.render_progressive(&mut framebuffer, &scene, &camera, None, |traced, progress| {
+                last_progress = Some(progress);
+                if progress.is_done() || last_present.elapsed() < STREAM_INTERVAL {
+                    return true;
+                }
+                // the rayon workers are all tracing: stretch the preview on this thread
+                upscale_sequential(traced, &mut display, resolution.filter);
+                display.swap_buffers_with(&mut window, &raylib_thread, |d| {
+                    hotbar::draw_hotbar(d, &hotbar, &scene.textures, crosshair);
+                    hud.draw(d, &hud_lines);
+                    hud::draw_progress(d, &progress);
+                });
+                last_present = Instant::now();
+                !input_arrived(&mut window, crosshair)
+            });
+            if !finished {
+                // the controller and the HUD still hear about the dropped frame: what the whole
+                // frame would have cost, from how far it got (and the input means the view changes)
+                let estimate = last_progress.map_or(renderer.stats.time, |p: Progress| p.elapsed + p.eta().unwrap_or_default());
+                resolution.update(estimate.as_secs_f32() * 1000.0, true);
+                hud.record(dt, hud::FrameTimes { trace: renderer.stats.time, sprites: sprites_time, present: present_time }, &renderer);
+                // skip presenting: this frame's key presses are still pending for the next one
+                continue;
+            }
+        }
+
+        if !scene.raytraced_particles {
+            let start = Instant::now();
+            render_sprites(&mut framebuffer, &scene.sprites.sprites, &camera, &scene.textures);
+            sprites_time += start.elapsed();
+        }
+        resolution.update(renderer.stats.time.as_secs_f32() * 1000.0, moving);
+        hud.record(dt, hud::FrameTimes { trace: renderer.stats.time, sprites: sprites_time, present: present_time }, &renderer);
+
+        // overlays go on top of the presented texture; the framebuffer itself is untouched
+        let start = Instant::now();
+        upscale(&framebuffer, &mut display, resolution.filter);
+        display.swap_buffers_with(&mut window, &raylib_thread, |d| {
+            hotbar::draw_hotbar(d, &hotbar, &scene.textures, crosshair);
+            hud.draw(d, &hud_lines);
+        });
+        present_time = start.elapsed();
+    }
+}
+
+// Keys that move the camera or change the view for as long as they are held
+const HELD_KEYS: [KeyboardKey; 16] = [
+    KeyboardKey::KEY_LEFT,
+    KeyboardKey::KEY_RIGHT,
+    KeyboardKey::KEY_UP,
+    KeyboardKey::KEY_DOWN,
+    KeyboardKey::KEY_W,
+    KeyboardKey::KEY_A,
+    KeyboardKey::KEY_S,
+    KeyboardKey::KEY_D,
+    KeyboardKey::KEY_E,
+    KeyboardKey::KEY_Q,
+    KeyboardKey::KEY_R,
+    KeyboardKey::KEY_F,
+    KeyboardKey::KEY_O,
+    KeyboardKey::KEY_P,
+    KeyboardKey::KEY_Z,
+    KeyboardKey::KEY_X,
+];
+
+// Input since the last poll that makes the frame being traced stale: a key or button press,
+// a held movement key, the wheel, or mouse look in fly mode (just moving the cursor in orbit
+// mode doesn't count)
+fn input_arrived(window: &mut RaylibHandle, fly: bool) -> bool {
+    window.get_key_pressed().is_some()
+        || HELD_KEYS.iter().any(|&k| window.is_key_down(k))
+        || window.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+        || window.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT)
+        || window.get_mouse_wheel_move() != 0.0
+        || (fly && window.get_mouse_delta() != Vector2::zero())
+}
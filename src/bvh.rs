@@ -0,0 +1,114 @@
+// bvh.rs
+use raylib::prelude::*;
+
+use crate::math::slab_hit;
+
+// -------- BVH --------
+
+#[derive(Clone, Copy, Debug)]
+struct BvhNode {
+    min: Vector3,
+    max: Vector3,
+    first: usize, // leaf: first entry in `items`; inner: index of the left child (right = left + 1)
+    count: usize, // > 0 for leaves
+}
+
+const LEAF_SIZE: usize = 4;
+const STACK_SIZE: usize = 64; // deeper than any median-split tree that fits in memory
+
+/// Bounding volume hierarchy over boxes tagged with the caller's indices (objects of a `Scene`,
+/// billboards of a particle cloud). Built with median splits, walked without allocating.
+#[derive(Clone, Debug, Default)]
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    items: Vec<usize>,
+}
+
+impl Bvh {
+    /// Builds the tree over `(index, (min, max))` pairs.
+    pub fn build(mut items: Vec<(usize, (Vector3, Vector3))>) -> Self {
+        let mut nodes = Vec::new();
+        if !items.is_empty() {
+            nodes.push(BvhNode { min: Vector3::zero(), max: Vector3::zero(), first: 0, count: items.len() });
+            subdivide(&mut nodes, &mut items, 0);
+        }
+        Bvh { nodes, items: items.into_iter().map(|(i, _)| i).collect() }
+    }
+
+    /// Box around everything, `None` when empty.
+    pub fn bounds(&self) -> Option<(Vector3, Vector3)> {
+        self.nodes.first().map(|n| (n.min, n.max))
+    }
+
+    /// Calls `f` with the index of every item whose leaf box the ray enters before `max_t`.
+    /// `f` may shrink `max_t` (closest-hit searches then skip farther boxes) and returns false
+    /// to stop. Returns how many nodes were tested.
+    pub fn visit(&self, ro: &Vector3, rd: &Vector3, mut max_t: f32, mut f: impl FnMut(usize, &mut f32) -> bool) -> u32 {
+        if self.nodes.is_empty() {
+            return 0;
+        }
+        let inv = Vector3::new(1.0 / rd.x, 1.0 / rd.y, 1.0 / rd.z);
+        // median splits keep the tree ~log2(n) deep, so a fixed stack is plenty (no allocation per ray)
+        let mut stack = [0usize; STACK_SIZE];
+        let mut len = 1;
+        let mut tests = 0;
+        while len > 0 {
+            len -= 1;
+            let node = self.nodes[stack[len]];
+            tests += 1;
+            if !slab_hit(&node.min, &node.max, ro, &inv, max_t) {
+                continue;
+            }
+            if node.count == 0 {
+                stack[len] = node.first;
+                stack[len + 1] = node.first + 1;
+                len += 2;
+                continue;
+            }
+            for &i in &self.items[node.first..node.first + node.count] {
+                if !f(i, &mut max_t) {
+                    return tests;
+                }
+            }
+        }
+        tests
+    }
+}
+
+// Median split on the longest axis of the centroids
+fn subdivide(nodes: &mut Vec<BvhNode>, items: &mut [(usize, (Vector3, Vector3))], node: usize) {
+    let BvhNode { first, count, .. } = nodes[node];
+    let slice = &mut items[first..first + count];
+
+    let (mut min, mut max) = slice[0].1;
+    for (_, (lo, hi)) in slice.iter() {
+        min = min.min(*lo);
+        max = max.max(*hi);
+    }
+    nodes[node].min = min;
+    nodes[node].max = max;
+    if count <= LEAF_SIZE {
+        return;
+    }
+
+    let ext = max - min;
+    let axis = if ext.x >= ext.y && ext.x >= ext.z { 0 } else if ext.y >= ext.z { 1 } else { 2 };
+    let key = |b: &(Vector3, Vector3)| {
+        let c = (b.0 + b.1) * 0.5;
+        match axis {
+            0 => c.x,
+            1 => c.y,
+            _ => c.z,
+        }
+    };
+    slice.sort_by(|a, b| key(&a.1).total_cmp(&key(&b.1)));
+
+    let half = count / 2;
+    let left = nodes.len();
+    nodes.push(BvhNode { min, max, first, count: half });
+    nodes.push(BvhNode { min, max, first: first + half, count: count - half });
+    nodes[node].first = left;
+    nodes[node].count = 0;
+    subdivide(nodes, items, left);
+    subdivide(nodes, items, left + 1);
+}
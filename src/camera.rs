@@ -2,7 +2,49 @@ use std::f32::consts::PI;
 
 use raylib::prelude::*;
 
+use crate::{
+    editor::{Face, Pick},
+    motion::Motion,
+    scene::Scene,
+};
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    Perspective,
+    Orthographic,    // parallel rays; view height follows the orbit distance
+    Equirectangular, // full 360° x 180° panorama, ignores fov
+    Fisheye,         // equidistant fisheye, `fov` is the diameter of the image circle
+    Cylindrical,     // panoramic horizontally, perspective vertically
+}
+
+impl Projection {
+    pub fn next(self) -> Self {
+        match self {
+            Projection::Perspective => Projection::Orthographic,
+            Projection::Orthographic => Projection::Equirectangular,
+            Projection::Equirectangular => Projection::Fisheye,
+            Projection::Fisheye => Projection::Cylindrical,
+            Projection::Cylindrical => Projection::Perspective,
+        }
+    }
+
+    /// Widest usable `fov`: a pinhole (or the vertical axis of the cylinder) blows up at 180°,
+    /// only the fisheye can cover the whole sphere.
+    pub fn max_fov(self) -> f32 {
+        match self {
+            Projection::Perspective | Projection::Orthographic | Projection::Cylindrical => PI - 0.01,
+            Projection::Equirectangular | Projection::Fisheye => 2.0 * PI,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CameraMode {
+    Orbit, // circles `center`
+    Fly,   // free first-person movement with mouse look
+}
+
+#[derive(Clone, Debug)]
 pub struct Camera {
     pub eye: Vector3,  // donde esta la camara en el mundo  7, 100, 10
     pub center: Vector3,     // que mira la camara  7, 100, 5
@@ -14,6 +56,29 @@ pub struct Camera {
 
     pub forward: Vector3,
     pub right: Vector3,
+
+    pub fov: f32,               // vertical field of view in radians
+    pub projection: Projection,
+
+    // Thin-lens depth of field (aperture 0 = pinhole)
+    pub aperture: f32,       // lens radius in world units
+    pub focal_distance: f32, // distance from the eye to the plane in focus
+    pub blade_count: u32,    // 0 = round bokeh, >=3 = polygonal aperture
+    pub lens_samples: u32,   // rays per pixel when the aperture is open
+    pub autofocus: bool,     // focus on whatever is under the screen centre
+
+    // First-person fly mode
+    pub mode: CameraMode,
+    pub move_speed: f32,       // world units per second
+    pub look_sensitivity: f32, // radians per pixel of mouse movement
+    pub collision: bool,       // stop at scene objects instead of flying through them
+    pub collision_radius: f32,
+
+    // Motion blur: rays get a time in [0, shutter] and the camera moves over it
+    pub shutter: f32,          // seconds the shutter stays open (0 = no motion blur)
+    pub time_samples: u32,     // rays per pixel spread over the shutter
+    pub eye_motion: Motion,
+    pub center_motion: Motion,
 }
 
 impl Camera {
@@ -43,6 +108,22 @@ impl Camera {
             pitch,
             forward: Vector3::zero(),
             right: Vector3::zero(),
+            fov: PI / 3.0,
+            projection: Projection::Perspective,
+            aperture: 0.0,
+            focal_distance: distance,
+            blade_count: 0,
+            lens_samples: 8,
+            autofocus: false,
+            mode: CameraMode::Orbit,
+            move_speed: 4.0,
+            look_sensitivity: 0.003,
+            collision: true,
+            collision_radius: 0.25,
+            shutter: 0.0,
+            time_samples: 8,
+            eye_motion: Motion::Static,
+            center_motion: Motion::Static,
         };
         camera.update_basis();
         camera
@@ -54,6 +135,75 @@ impl Camera {
         self.up = self.right.cross(self.forward);
     }
 
+    /// Switches between orbiting `center` and flying freely.
+    pub fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            CameraMode::Orbit => CameraMode::Fly,
+            CameraMode::Fly => CameraMode::Orbit,
+        };
+        // Orbit keeps the distance it had; just make sure the angles match the current view
+        let offset = self.eye - self.center;
+        let radius = offset.length().max(1e-4);
+        self.pitch = (offset.y / radius).clamp(-1.0, 1.0).asin();
+        self.yaw = offset.z.atan2(offset.x);
+    }
+
+    /// Mouse look in fly mode: rotates the view around the eye.
+    /// Positive `yaw` turns right, positive `pitch` looks down.
+    pub fn look(&mut self, yaw: f32, pitch: f32) {
+        let radius = (self.center - self.eye).length().max(1e-4);
+
+        // Same spherical angles as `orbit`, but the eye stays put and `center` moves.
+        // Pitch is clamped short of the poles so `update_basis` never sees forward == up.
+        let limit = PI * 0.5 - 0.01;
+        self.yaw += yaw;
+        self.pitch = (self.pitch + pitch).clamp(-limit, limit);
+
+        let pitch_cos = self.pitch.cos();
+        let offset = Vector3::new(
+            radius * pitch_cos * self.yaw.cos(),
+            radius * self.pitch.sin(),
+            radius * pitch_cos * self.yaw.sin(),
+        );
+        self.center = self.eye - offset;
+
+        self.up = Vector3::new(0.0, 1.0, 0.0);
+        self.update_basis();
+    }
+
+    /// Moves eye and center together by `delta` (world space).
+    /// With `collision` on, the move stops short of the first object hit and slides along it.
+    pub fn fly(&mut self, delta: Vector3, scene: &Scene) {
+        let mut remaining = delta;
+
+        // two passes: move until contact, then slide along the surface
+        for _ in 0..2 {
+            let len = remaining.length();
+            if len <= 1e-6 {
+                break;
+            }
+            let dir = remaining / len;
+
+            let mut step = remaining;
+            let mut slide = Vector3::zero();
+            if self.collision {
+                let closest = scene.intersect_objects(&self.eye, &dir, 0.0);
+                if closest.is_intersecting && closest.distance < len + self.collision_radius {
+                    let allowed = (closest.distance - self.collision_radius).max(0.0);
+                    step = dir * allowed;
+                    let rest = remaining - step;
+                    slide = rest - closest.normal * rest.dot(closest.normal);
+                }
+            }
+
+            self.eye += step;
+            self.center += step;
+            remaining = slide;
+        }
+
+        self.update_basis();
+    }
+
     pub fn orbit(&mut self, yaw: f32, pitch: f32) {
         let relative_pos = self.eye - self.center;
 
@@ -122,4 +272,217 @@ impl Camera {
             -rel.dot(self.forward),
         )
     }
-}
\ No newline at end of file
+
+    /// Maps two uniform numbers in [0,1) to a point on the unit aperture,
+    /// a disc or a regular polygon with `blade_count` sides.
+    pub fn sample_aperture(&self, u1: f32, u2: f32) -> Vector2 {
+        if self.blade_count < 3 {
+            // uniform disc
+            let r = u1.sqrt();
+            let phi = 2.0 * PI * u2;
+            return Vector2::new(r * phi.cos(), r * phi.sin());
+        }
+
+        // pick one triangular wedge of the polygon, then a uniform point inside it
+        let n = self.blade_count as f32;
+        let wedge = (u1 * n).floor().min(n - 1.0);
+        let u1 = u1 * n - wedge; // reuse the fractional part
+        let a0 = 2.0 * PI * wedge / n;
+        let a1 = 2.0 * PI * (wedge + 1.0) / n;
+        let p0 = Vector2::new(a0.cos(), a0.sin());
+        let p1 = Vector2::new(a1.cos(), a1.sin());
+
+        let su = u1.sqrt();
+        let b0 = su * (1.0 - u2);
+        let b1 = su * u2;
+        p0 * b0 + p1 * b1
+    }
+
+    /// Half height of the orthographic view volume, matched to the perspective view at `center`.
+    fn ortho_half_height(&self) -> f32 {
+        (self.center - self.eye).length() * (self.fov * 0.5).tan()
+    }
+
+    /// Camera-space direction for a point in normalized device coordinates ([-1,1], y up).
+    /// Returns `None` outside the image circle of the fisheye.
+    pub fn camera_dir(&self, ndc_x: f32, ndc_y: f32, aspect: f32) -> Option<Vector3> {
+        let scale = (self.fov * 0.5).tan();
+        match self.projection {
+            Projection::Perspective | Projection::Orthographic => {
+                Some(Vector3::new(ndc_x * aspect * scale, ndc_y * scale, -1.0))
+            }
+            Projection::Equirectangular => {
+                let lon = ndc_x * PI;
+                let lat = ndc_y * PI * 0.5;
+                Some(Vector3::new(lon.sin() * lat.cos(), lat.sin(), -lon.cos() * lat.cos()))
+            }
+            Projection::Fisheye => {
+                let x = ndc_x * aspect;
+                let r = (x * x + ndc_y * ndc_y).sqrt();
+                if r > 1.0 {
+                    return None;
+                }
+                let theta = r * self.fov * 0.5;
+                if r < 1e-6 {
+                    return Some(Vector3::new(0.0, 0.0, -1.0));
+                }
+                let s = theta.sin() / r;
+                Some(Vector3::new(x * s, ndc_y * s, -theta.cos()))
+            }
+            Projection::Cylindrical => {
+                let lon = ndc_x * aspect * self.fov * 0.5;
+                Some(Vector3::new(lon.sin(), ndc_y * scale, -lon.cos()))
+            }
+        }
+    }
+
+    /// Primary ray for a pixel in NDC and a lens sample, honouring projection and depth of field.
+    /// Returns (origin, direction) in world space.
+    pub fn primary_ray(&self, ndc_x: f32, ndc_y: f32, aspect: f32, lens: Vector2) -> Option<(Vector3, Vector3)> {
+        if self.projection == Projection::Orthographic {
+            let h = self.ortho_half_height();
+            let offset = Vector3::new(ndc_x * aspect * h, ndc_y * h, 0.0);
+            return Some((self.eye + self.basis_change(&offset), self.forward));
+        }
+        let rd_cam = self.camera_dir(ndc_x, ndc_y, aspect)?;
+        Some(self.lens_ray(&rd_cam, lens))
+    }
+
+    /// Thin-lens ray for a camera-space direction and a lens sample.
+    /// Returns (origin, direction) in world space.
+    pub fn lens_ray(&self, rd_cam: &Vector3, lens: Vector2) -> (Vector3, Vector3) {
+        if self.aperture <= 0.0 {
+            return (self.eye, self.basis_change(rd_cam).normalized());
+        }
+
+        // Point in focus along the pinhole ray, in camera space:
+        // a focal plane for perspective, a focal sphere for the panoramic projections
+        let focus = if self.projection == Projection::Perspective && rd_cam.z < -1e-4 {
+            *rd_cam * (self.focal_distance / -rd_cam.z)
+        } else {
+            rd_cam.normalized() * self.focal_distance
+        };
+        let lens_cam = Vector3::new(lens.x * self.aperture, lens.y * self.aperture, 0.0);
+
+        let origin = self.eye + self.basis_change(&lens_cam);
+        let dir = self.basis_change(&(focus - lens_cam)).normalized();
+        (origin, dir)
+    }
+
+    /// World -> normalized device coordinates plus depth, the inverse of `primary_ray`.
+    pub fn project(&self, p_world: &Vector3, aspect: f32) -> Option<(f32, f32, f32)> {
+        let p_cam = self.world_to_camera(p_world);
+        let scale = (self.fov * 0.5).tan();
+        let dist = p_cam.length();
+        if dist <= 1e-6 {
+            return None;
+        }
+        let d = p_cam / dist;
+
+        let (x_ndc, y_ndc, depth) = match self.projection {
+            Projection::Perspective => {
+                // Behind camera?
+                if p_cam.z >= 0.0 {
+                    return None;
+                }
+                ((p_cam.x / -p_cam.z) / (aspect * scale), (p_cam.y / -p_cam.z) / scale, -p_cam.z)
+            }
+            Projection::Orthographic => {
+                if p_cam.z >= 0.0 {
+                    return None;
+                }
+                let h = self.ortho_half_height();
+                (p_cam.x / (aspect * h), p_cam.y / h, -p_cam.z)
+            }
+            Projection::Equirectangular => {
+                let lon = d.x.atan2(-d.z);
+                let lat = d.y.clamp(-1.0, 1.0).asin();
+                (lon / PI, lat / (PI * 0.5), dist)
+            }
+            Projection::Fisheye => {
+                let theta = (-d.z).clamp(-1.0, 1.0).acos();
+                let r = theta / (self.fov * 0.5);
+                let phi = d.y.atan2(d.x);
+                ((r * phi.cos()) / aspect, r * phi.sin(), dist)
+            }
+            Projection::Cylindrical => {
+                let horizontal = (p_cam.x * p_cam.x + p_cam.z * p_cam.z).sqrt();
+                if horizontal <= 1e-6 {
+                    return None;
+                }
+                let lon = p_cam.x.atan2(-p_cam.z);
+                (lon / (aspect * self.fov * 0.5), (p_cam.y / horizontal) / scale, horizontal)
+            }
+        };
+
+        if !(-1.0..=1.0).contains(&x_ndc) || !(-1.0..=1.0).contains(&y_ndc) {
+            return None;
+        }
+        Some((x_ndc, y_ndc, depth))
+    }
+
+    /// Depth of a world point in the same units `project` returns (for depth buffers).
+    pub fn view_depth(&self, p_world: &Vector3) -> f32 {
+        let p_cam = self.world_to_camera(p_world);
+        match self.projection {
+            Projection::Perspective | Projection::Orthographic => -p_cam.z,
+            Projection::Equirectangular | Projection::Fisheye => p_cam.length(),
+            Projection::Cylindrical => (p_cam.x * p_cam.x + p_cam.z * p_cam.z).sqrt(),
+        }
+    }
+
+    /// Approximate size on screen (in pixels) of one world unit seen at `depth`.
+    pub fn pixels_per_unit(&self, depth: f32, screen_height: f32) -> f32 {
+        let depth = depth.max(1e-3);
+        match self.projection {
+            Projection::Perspective | Projection::Cylindrical => {
+                screen_height / (2.0 * (self.fov * 0.5).tan() * depth)
+            }
+            Projection::Orthographic => screen_height / (2.0 * self.ortho_half_height()),
+            Projection::Equirectangular => screen_height / (PI * depth),
+            Projection::Fisheye => screen_height / (self.fov * depth),
+        }
+    }
+
+    /// The camera as it is `time` seconds after the shutter opens.
+    pub fn at_time(&self, time: f32) -> Camera {
+        let mut cam = self.clone();
+        if self.eye_motion.is_static() && self.center_motion.is_static() {
+            return cam;
+        }
+        cam.eye += self.eye_motion.offset(time);
+        cam.center += self.center_motion.offset(time);
+        cam.update_basis();
+        cam
+    }
+
+    /// Makes the camera travel from a previous eye/center to the current one while the shutter is open,
+    /// so interactive orbiting smears the image in the direction of motion.
+    pub fn set_motion_from_previous(&mut self, prev_eye: Vector3, prev_center: Vector3) {
+        self.eye_motion = Motion::keyframed(&[(0.0, prev_eye - self.eye), (self.shutter, Vector3::zero())]);
+        self.center_motion = Motion::keyframed(&[(0.0, prev_center - self.center), (self.shutter, Vector3::zero())]);
+    }
+
+    /// Object and face under a point of the image (NDC, y up): the pinhole ray through
+    /// `basis_change`, without depth of field. Particles can't be picked.
+    pub fn pick(&self, scene: &Scene, ndc_x: f32, ndc_y: f32, aspect: f32) -> Option<Pick> {
+        let (ro, rd) = self.primary_ray(ndc_x, ndc_y, aspect, Vector2::zero())?;
+        let hit = scene.intersect_objects(&ro, &rd, 0.0);
+        hit.is_intersecting.then(|| Pick {
+            id: hit.obj_id,
+            face: Face::from_normal(hit.normal),
+            point: hit.point,
+            normal: hit.normal,
+            distance: hit.distance,
+            material: hit.material,
+        })
+    }
+
+    /// Sets `focal_distance` to the nearest object under the screen centre.
+    pub fn focus_on_center(&mut self, scene: &Scene) {
+        let closest = scene.intersect_objects(&self.eye, &self.forward, 0.0);
+        if closest.is_intersecting {
+            self.focal_distance = closest.distance;
+        }
+    }
+}
@@ -0,0 +1,219 @@
+// clouds.rs
+use raylib::prelude::*;
+
+use crate::{color::srgb_to_linear, light::PointLight};
+
+// Capa de nubes volumétricas (slab horizontal entre `altitude` y `altitude + thickness`)
+#[derive(Clone, Copy, Debug)]
+pub struct CloudLayer {
+    pub coverage: f32,       // 0..1, fraction of the sky covered
+    pub density: f32,        // extinction per world unit inside a cloud
+    pub altitude: f32,       // bottom of the cloud slab (world y)
+    pub thickness: f32,      // height of the slab
+    pub wind_speed: f32,     // world units per second
+    pub wind_dir: Vector3,   // horizontal drift direction
+    pub scale: f32,          // world units per noise cell
+    pub steps: u32,          // raymarch steps along the view ray
+    pub light_steps: u32,    // raymarch steps towards the sun/moon (self-shadowing)
+}
+
+impl Default for CloudLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CloudLayer {
+    pub fn new() -> Self {
+        CloudLayer {
+            coverage: 0.45,
+            density: 0.35,
+            altitude: 30.0,
+            thickness: 12.0,
+            wind_speed: 2.0,
+            wind_dir: Vector3::new(1.0, 0.0, 0.3).normalized(),
+            scale: 18.0,
+            steps: 12,
+            light_steps: 3,
+        }
+    }
+
+    /// Cloud density at a world-space point, animated by `elapsed` seconds of wind.
+    pub fn density_at(&self, p: Vector3, elapsed: f32) -> f32 {
+        let top = self.altitude + self.thickness;
+        if p.y < self.altitude || p.y > top || self.coverage <= 0.0 {
+            return 0.0;
+        }
+
+        let drift = self.wind_dir * (self.wind_speed * elapsed);
+        let q = (p - drift) / self.scale;
+        let n = fbm(q, 4);
+
+        // Coverage remaps the noise threshold: more coverage → lower threshold
+        let threshold = 1.0 - self.coverage;
+        let shape = ((n - threshold) / (1.0 - threshold).max(1e-3)).clamp(0.0, 1.0);
+
+        // Round off the bottom and top of the slab so clouds don't look sliced
+        let h = (p.y - self.altitude) / self.thickness; // 0..1
+        let profile = (h * 4.0).min(1.0) * ((1.0 - h) * 2.0).min(1.0);
+
+        shape * profile * self.density
+    }
+
+    /// Parametric entry/exit of a ray with the cloud slab, if any.
+    fn slab_span(&self, ro: Vector3, rd: Vector3) -> Option<(f32, f32)> {
+        if rd.y.abs() < 1e-4 {
+            return None;
+        }
+        let top = self.altitude + self.thickness;
+        let mut t0 = (self.altitude - ro.y) / rd.y;
+        let mut t1 = (top - ro.y) / rd.y;
+        if t0 > t1 {
+            std::mem::swap(&mut t0, &mut t1);
+        }
+        let t0 = t0.max(0.0);
+        if t1 <= t0 {
+            return None;
+        }
+        // Very grazing rays travel huge distances through the slab; cap them
+        let max_len = self.thickness * 8.0;
+        Some((t0, t1.min(t0 + max_len)))
+    }
+
+    /// Fraction of light that gets through the layer from `p` along `dir` (Beer–Lambert).
+    pub fn transmittance(&self, p: Vector3, dir: Vector3, elapsed: f32, steps: u32) -> f32 {
+        let Some((t0, t1)) = self.slab_span(p, dir) else {
+            return 1.0;
+        };
+        let steps = steps.max(1);
+        let dt = (t1 - t0) / steps as f32;
+        let mut optical_depth = 0.0;
+        for i in 0..steps {
+            let t = t0 + (i as f32 + 0.5) * dt;
+            optical_depth += self.density_at(p + dir * t, elapsed) * dt;
+        }
+        (-optical_depth).exp()
+    }
+
+    /// Raymarches the layer along a view ray.
+    /// Returns (in-scattered linear color, remaining transmittance of the background).
+    pub fn march(
+        &self,
+        ro: Vector3,
+        rd: Vector3,
+        elapsed: f32,
+        sun: &PointLight,
+        moon: &PointLight,
+        ambient: Vector3,
+    ) -> (Vector3, f32) {
+        let Some((t0, t1)) = self.slab_span(ro, rd) else {
+            return (Vector3::zero(), 1.0);
+        };
+
+        let lights = [sun, moon];
+        let light_dirs = [sun.position.normalized(), moon.position.normalized()];
+
+        let steps = self.steps.max(1);
+        let dt = (t1 - t0) / steps as f32;
+        let mut transmittance = 1.0;
+        let mut scattered = Vector3::zero();
+
+        for i in 0..steps {
+            let t = t0 + (i as f32 + 0.5) * dt;
+            let p = ro + rd * t;
+            let sigma = self.density_at(p, elapsed);
+            if sigma <= 0.0 {
+                continue;
+            }
+
+            // Light reaching this sample from the sun and the moon
+            let mut light = ambient;
+            for (l, l_dir) in lights.iter().zip(light_dirs.iter()) {
+                if l.intensity <= 0.0 || l_dir.y <= 0.0 {
+                    continue;
+                }
+                let (lr, lg, lb) = srgb_to_linear(l.color);
+                let shadow = self.transmittance(p, *l_dir, elapsed, self.light_steps);
+                let phase = henyey_greenstein(rd.dot(*l_dir), 0.3);
+                let k = l.intensity * shadow * phase;
+                light += Vector3::new(lr, lg, lb) * k;
+            }
+
+            // Energy-conserving integration of one step (Hillaire)
+            let step_t = (-sigma * dt).exp();
+            scattered += light * (transmittance * (1.0 - step_t));
+            transmittance *= step_t;
+
+            if transmittance < 0.01 {
+                break;
+            }
+        }
+
+        // Fade clouds out towards the horizon so the far slab doesn't end abruptly
+        let horizon_fade = (rd.y * 6.0).clamp(0.0, 1.0);
+        (
+            scattered * horizon_fade,
+            1.0 - (1.0 - transmittance) * horizon_fade,
+        )
+    }
+}
+
+// Forward-scattering phase function; normalized so isotropic ≈ 1.0
+pub fn henyey_greenstein(cos_theta: f32, g: f32) -> f32 {
+    let g2 = g * g;
+    let denom = (1.0 + g2 - 2.0 * g * cos_theta).max(1e-4);
+    (1.0 - g2) / (denom * denom.sqrt())
+}
+
+// -------- 3D value noise --------
+fn hash3(x: i32, y: i32, z: i32) -> f32 {
+    let mut h = (x as u32).wrapping_mul(0x8da6_b343)
+        ^ (y as u32).wrapping_mul(0xd816_3841)
+        ^ (z as u32).wrapping_mul(0xcb1a_b31f);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0x5bd1_e995);
+    h ^= h >> 15;
+    (h & 0x00ff_ffff) as f32 / 0x00ff_ffff as f32
+}
+
+pub fn value_noise(p: Vector3) -> f32 {
+    let (xf, yf, zf) = (p.x.floor(), p.y.floor(), p.z.floor());
+    let (xi, yi, zi) = (xf as i32, yf as i32, zf as i32);
+
+    // smooth interpolation weights
+    let fade = |t: f32| t * t * (3.0 - 2.0 * t);
+    let (u, v, w) = (fade(p.x - xf), fade(p.y - yf), fade(p.z - zf));
+
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+
+    let c000 = hash3(xi, yi, zi);
+    let c100 = hash3(xi + 1, yi, zi);
+    let c010 = hash3(xi, yi + 1, zi);
+    let c110 = hash3(xi + 1, yi + 1, zi);
+    let c001 = hash3(xi, yi, zi + 1);
+    let c101 = hash3(xi + 1, yi, zi + 1);
+    let c011 = hash3(xi, yi + 1, zi + 1);
+    let c111 = hash3(xi + 1, yi + 1, zi + 1);
+
+    let x00 = lerp(c000, c100, u);
+    let x10 = lerp(c010, c110, u);
+    let x01 = lerp(c001, c101, u);
+    let x11 = lerp(c011, c111, u);
+
+    lerp(lerp(x00, x10, v), lerp(x01, x11, v), w)
+}
+
+// Fractal Brownian motion: sum of octaves, normalized to ~[0,1]
+pub fn fbm(p: Vector3, octaves: u32) -> f32 {
+    let mut sum = 0.0;
+    let mut amp = 0.5;
+    let mut norm = 0.0;
+    let mut q = p;
+    for _ in 0..octaves {
+        sum += value_noise(q) * amp;
+        norm += amp;
+        q *= 2.03;
+        amp *= 0.5;
+    }
+    sum / norm
+}
@@ -0,0 +1,247 @@
+// denoise.rs
+use raylib::prelude::*;
+use rayon::prelude::*;
+
+use crate::{
+    aov::AovBuffer,
+    camera::Camera,
+    temporal::reproject,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DenoiseMode {
+    Off,
+    ATrous, // edge-avoiding À-Trous wavelet filter, spatial only
+    Svgf,   // temporal accumulation + variance-guided À-Trous
+}
+
+impl DenoiseMode {
+    pub fn next(self) -> Self {
+        match self {
+            DenoiseMode::Off => DenoiseMode::ATrous,
+            DenoiseMode::ATrous => DenoiseMode::Svgf,
+            DenoiseMode::Svgf => DenoiseMode::Off,
+        }
+    }
+}
+
+// B3-spline taps for offsets 0, ±1, ±2
+const KERNEL: [f32; 3] = [3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+pub struct Denoiser {
+    pub mode: DenoiseMode,
+    pub iterations: u32,     // À-Trous passes; pass i uses a step of 2^i pixels
+    pub sigma_color: f32,    // luminance edge stopping, in standard deviations
+    pub sigma_normal: f32,   // exponent on n·n', higher = sharper creases
+    pub sigma_depth: f32,    // allowed relative depth change per step
+    pub temporal_alpha: f32, // minimum weight of the new frame when accumulating
+    pub max_history: f32,    // frames after which the history stops growing
+
+    history: Vec<Vector3>, // accumulated illumination (albedo divided out)
+    moments: Vec<Vector2>, // accumulated luminance moments (l, l²)
+    history_len: Vec<f32>,
+    prev_gbuffer: Option<AovBuffer>,
+    prev_camera: Option<Camera>,
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Denoiser {
+    pub fn new() -> Self {
+        Denoiser {
+            mode: DenoiseMode::Off,
+            iterations: 4,
+            sigma_color: 4.0,
+            sigma_normal: 128.0,
+            sigma_depth: 0.05,
+            temporal_alpha: 0.2,
+            max_history: 32.0,
+            history: Vec::new(),
+            moments: Vec::new(),
+            history_len: Vec::new(),
+            prev_gbuffer: None,
+            prev_camera: None,
+        }
+    }
+
+    /// Forgets the temporal history (after a cut, a resize or switching modes).
+    pub fn reset(&mut self) {
+        self.history.clear();
+        self.moments.clear();
+        self.history_len.clear();
+        self.prev_gbuffer = None;
+        self.prev_camera = None;
+    }
+
+    /// Filters the linear `color` buffer in place, guided by the albedo/normal/depth AOVs of the same frame.
+    pub fn denoise(&mut self, color: &mut [Vector3], gbuf: &AovBuffer, camera: &Camera) {
+        if self.mode == DenoiseMode::Off {
+            self.reset();
+            return;
+        }
+
+        // Filter illumination only, so textures stay sharp
+        let mut illum: Vec<Vector3> = color
+            .iter()
+            .zip(&gbuf.samples)
+            .map(|(c, g)| demodulate(*c, g.albedo))
+            .collect();
+
+        let mut variance = match self.mode {
+            DenoiseMode::Svgf => self.accumulate(&mut illum, gbuf, camera),
+            _ => {
+                self.reset();
+                spatial_variance(&illum, gbuf)
+            }
+        };
+
+        for i in 0..self.iterations {
+            let (next_illum, next_var) = self.atrous_pass(&illum, &variance, gbuf, 1 << i);
+            illum = next_illum;
+            variance = next_var;
+            // SVGF feeds the first filtered level back as history: stable but not over-blurred
+            if i == 0 && self.mode == DenoiseMode::Svgf {
+                self.history.clone_from(&illum);
+            }
+        }
+
+        for ((c, l), g) in color.iter_mut().zip(&illum).zip(&gbuf.samples) {
+            if !g.is_sky() {
+                *c = Vector3::new(l.x * g.albedo.x, l.y * g.albedo.y, l.z * g.albedo.z);
+            }
+        }
+    }
+
+    // Blends this frame into the reprojected history; returns the per-pixel luminance variance.
+    fn accumulate(&mut self, illum: &mut [Vector3], gbuf: &AovBuffer, camera: &Camera) -> Vec<f32> {
+        let n = gbuf.width * gbuf.height;
+        if self.history.len() != n {
+            self.reset();
+        }
+        let prev = self.prev_gbuffer.as_ref().zip(self.prev_camera.as_ref());
+
+        let results: Vec<(Vector3, Vector2, f32)> = (0..n)
+            .into_par_iter()
+            .map(|i| {
+                let g = &gbuf.samples[i];
+                let c = illum[i];
+                let l = luminance(c);
+                let m = Vector2::new(l, l * l);
+                if g.is_sky() {
+                    return (c, m, 0.0);
+                }
+                if let Some(j) = prev.and_then(|(pg, pc)| reproject(g, pg, pc)) {
+                    let len = (self.history_len[j] + 1.0).min(self.max_history);
+                    let alpha = (1.0 / len).max(self.temporal_alpha);
+                    return (self.history[j].lerp(c, alpha), self.moments[j].lerp(m, alpha), len);
+                }
+                (c, m, 1.0)
+            })
+            .collect();
+
+        let spatial = spatial_variance(illum, gbuf);
+        let mut variance = vec![0.0; n];
+        self.history = Vec::with_capacity(n);
+        self.moments = Vec::with_capacity(n);
+        self.history_len = Vec::with_capacity(n);
+        for (i, (c, m, len)) in results.into_iter().enumerate() {
+            illum[i] = c;
+            // Young history has unreliable moments: fall back to the neighbourhood
+            variance[i] = if len >= 4.0 { (m.y - m.x * m.x).max(0.0) } else { spatial[i] };
+            self.history.push(c);
+            self.moments.push(m);
+            self.history_len.push(len);
+        }
+
+        self.prev_gbuffer = Some(gbuf.clone());
+        self.prev_camera = Some(camera.clone());
+        variance
+    }
+
+    // One edge-avoiding À-Trous pass (5x5 taps spread `step` pixels apart)
+    fn atrous_pass(&self, illum: &[Vector3], variance: &[f32], gbuf: &AovBuffer, step: i32) -> (Vec<Vector3>, Vec<f32>) {
+        let (w, h) = (gbuf.width as i32, gbuf.height as i32);
+
+        (0..gbuf.width * gbuf.height)
+            .into_par_iter()
+            .map(|i| {
+                let gp = &gbuf.samples[i];
+                if gp.is_sky() {
+                    return (illum[i], variance[i]);
+                }
+                let (x, y) = ((i % gbuf.width) as i32, (i / gbuf.width) as i32);
+                let lp = luminance(illum[i]);
+                let std = variance[i].max(0.0).sqrt();
+
+                let mut sum_c = Vector3::zero();
+                let mut sum_v = 0.0;
+                let mut sum_w = 0.0;
+                for dy in -2..=2_i32 {
+                    for dx in -2..=2_i32 {
+                        let (qx, qy) = (x + dx * step, y + dy * step);
+                        if qx < 0 || qy < 0 || qx >= w || qy >= h {
+                            continue;
+                        }
+                        let q = (qy * w + qx) as usize;
+                        let gq = &gbuf.samples[q];
+                        if gq.is_sky() {
+                            continue;
+                        }
+
+                        let w_n = gp.normal.dot(gq.normal).max(0.0).powf(self.sigma_normal);
+                        let w_z = (-(gp.depth - gq.depth).abs() / (self.sigma_depth * gp.depth * step as f32 + 1e-4)).exp();
+                        let w_l = (-(lp - luminance(illum[q])).abs() / (self.sigma_color * std + 1e-4)).exp();
+                        let wq = KERNEL[dx.unsigned_abs() as usize] * KERNEL[dy.unsigned_abs() as usize] * w_n * w_z * w_l;
+
+                        sum_c += illum[q] * wq;
+                        sum_v += variance[q] * wq * wq;
+                        sum_w += wq;
+                    }
+                }
+                // the centre tap always has weight > 0
+                (sum_c / sum_w, sum_v / (sum_w * sum_w))
+            })
+            .unzip()
+    }
+}
+
+// 3x3 luminance variance over surface pixels
+fn spatial_variance(illum: &[Vector3], gbuf: &AovBuffer) -> Vec<f32> {
+    let (w, h) = (gbuf.width as i32, gbuf.height as i32);
+    (0..gbuf.width * gbuf.height)
+        .into_par_iter()
+        .map(|i| {
+            let (x, y) = ((i % gbuf.width) as i32, (i / gbuf.width) as i32);
+            let (mut m1, mut m2, mut count) = (0.0, 0.0, 0.0);
+            for qy in (y - 1).max(0)..=(y + 1).min(h - 1) {
+                for qx in (x - 1).max(0)..=(x + 1).min(w - 1) {
+                    let q = (qy * w + qx) as usize;
+                    if gbuf.samples[q].is_sky() {
+                        continue;
+                    }
+                    let l = luminance(illum[q]);
+                    m1 += l;
+                    m2 += l * l;
+                    count += 1.0;
+                }
+            }
+            if count == 0.0 {
+                return 0.0;
+            }
+            let mean = m1 / count;
+            (m2 / count - mean * mean).max(0.0)
+        })
+        .collect()
+}
+
+fn demodulate(c: Vector3, albedo: Vector3) -> Vector3 {
+    Vector3::new(c.x / albedo.x.max(1e-3), c.y / albedo.y.max(1e-3), c.z / albedo.z.max(1e-3))
+}
+
+pub fn luminance(c: Vector3) -> f32 {
+    0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z
+}
@@ -0,0 +1,269 @@
+// editor.rs
+use std::io::{self, BufRead, BufWriter, Write};
+
+use raylib::prelude::*;
+
+use crate::{
+    material::Material,
+    object::{Cube, Object},
+    occlusion::unit_cell,
+    ray_intersect::ObjectId,
+    scene::Scene,
+};
+
+// -------- Edición de bloques --------
+
+/// Side of a block a ray hit, from the hit normal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Face {
+    East,  // +X
+    West,  // -X
+    Up,    // +Y
+    Down,  // -Y
+    South, // +Z
+    North, // -Z
+}
+
+impl Face {
+    pub fn from_normal(n: Vector3) -> Face {
+        let (ax, ay, az) = (n.x.abs(), n.y.abs(), n.z.abs());
+        if ax >= ay && ax >= az {
+            if n.x > 0.0 { Face::East } else { Face::West }
+        } else if ay >= az {
+            if n.y > 0.0 { Face::Up } else { Face::Down }
+        } else if n.z > 0.0 {
+            Face::South
+        } else {
+            Face::North
+        }
+    }
+
+    /// Step to the neighbouring cell on this side.
+    pub fn offset(self) -> [i32; 3] {
+        match self {
+            Face::East => [1, 0, 0],
+            Face::West => [-1, 0, 0],
+            Face::Up => [0, 1, 0],
+            Face::Down => [0, -1, 0],
+            Face::South => [0, 0, 1],
+            Face::North => [0, 0, -1],
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Face::East => "east",
+            Face::West => "west",
+            Face::Up => "up",
+            Face::Down => "down",
+            Face::South => "south",
+            Face::North => "north",
+        }
+    }
+}
+
+/// What the cursor is over: see `Camera::pick`.
+#[derive(Clone, Copy, Debug)]
+pub struct Pick {
+    pub id: ObjectId,
+    pub face: Face,
+    pub point: Vector3,
+    pub normal: Vector3,
+    pub distance: f32,
+    pub material: Material,
+}
+
+/// Blocks the hotbar can place.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockType {
+    Stone,
+    Obsidian,
+    Dirt,
+    Glass,
+    Lamp,
+}
+
+impl BlockType {
+    pub const HOTBAR: [BlockType; 5] = [BlockType::Stone, BlockType::Obsidian, BlockType::Dirt, BlockType::Glass, BlockType::Lamp];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BlockType::Stone => "stone",
+            BlockType::Obsidian => "obsidian",
+            BlockType::Dirt => "dirt",
+            BlockType::Glass => "glass",
+            BlockType::Lamp => "lamp",
+        }
+    }
+
+    /// Texture id, also the key `Cube::new` builds the block from.
+    pub fn texture(self) -> char {
+        match self {
+            BlockType::Stone => '+',
+            BlockType::Obsidian => '-',
+            BlockType::Dirt => '#',
+            BlockType::Glass => 't',
+            BlockType::Lamp => 'l',
+        }
+    }
+
+    pub fn cube(self, cell: [i32; 3]) -> Cube {
+        Cube::new(cell[0] as f32, cell[1] as f32, cell[2] as f32, self.texture())
+    }
+}
+
+/// Selected slot of `BlockType::HOTBAR`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Hotbar {
+    pub selected: usize,
+}
+
+impl Hotbar {
+    pub fn new() -> Self {
+        Hotbar { selected: 0 }
+    }
+
+    pub fn block(&self) -> BlockType {
+        BlockType::HOTBAR[self.selected % BlockType::HOTBAR.len()]
+    }
+
+    pub fn select(&mut self, slot: usize) {
+        if slot < BlockType::HOTBAR.len() {
+            self.selected = slot;
+        }
+    }
+
+    /// Mouse wheel: positive steps move right, wrapping around.
+    pub fn scroll(&mut self, steps: i32) {
+        let n = BlockType::HOTBAR.len() as i32;
+        self.selected = (self.selected as i32 + steps).rem_euclid(n) as usize;
+    }
+}
+
+/// Removes the picked block. Only blocks (`Object::Cube`) can be broken.
+pub fn break_block(scene: &mut Scene, pick: &Pick) -> Option<Object> {
+    if !matches!(scene.get(pick.id), Some(Object::Cube(_))) {
+        return None;
+    }
+    scene.remove(pick.id)
+}
+
+/// Places `block` against the picked face (or in the cell in front of a non-block surface).
+/// Returns None if that cell is already taken.
+pub fn place_block(scene: &mut Scene, pick: &Pick, block: BlockType) -> Option<ObjectId> {
+    let cell = match scene.get(pick.id).and_then(block_cell) {
+        Some(c) => {
+            let d = pick.face.offset();
+            [c[0] + d[0], c[1] + d[1], c[2] + d[2]]
+        }
+        None => {
+            let p = pick.point + pick.normal * 0.5;
+            [p.x.floor() as i32, p.y.floor() as i32, p.z.floor() as i32]
+        }
+    };
+    if scene.objects().any(|(_, obj)| block_cell(obj) == Some(cell)) {
+        return None;
+    }
+    Some(scene.add(Object::Cube(block.cube(cell))))
+}
+
+// -------- Guardar / cargar --------
+
+/// Writes every block of the scene as `x y z texture` lines (`g` = grass block).
+/// The rest of the scene (meshes, portal, sphere) comes from code and isn't saved.
+pub fn save_blocks(scene: &Scene, path: &str) -> io::Result<()> {
+    let mut out = BufWriter::new(std::fs::File::create(path)?);
+    writeln!(out, "# x y z block")?;
+    for (_, obj) in scene.objects() {
+        if let (Some(cell), Some(key)) = (block_cell(obj), block_key(obj)) {
+            writeln!(out, "{} {} {} {}", cell[0], cell[1], cell[2], key)?;
+        }
+    }
+    out.flush()
+}
+
+/// Replaces the scene's blocks with the ones in a `save_blocks` file. Returns how many were loaded.
+pub fn load_blocks(scene: &mut Scene, path: &str) -> io::Result<usize> {
+    let mut blocks = Vec::new();
+    for (n, line) in io::BufReader::new(std::fs::File::open(path)?).lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let bad = || io::Error::new(io::ErrorKind::InvalidData, format!("{}:{}: expected `x y z block`", path, n + 1));
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [x, y, z, key] = fields[..] else { return Err(bad()) };
+        let (Ok(x), Ok(y), Ok(z)) = (x.parse::<i32>(), y.parse::<i32>(), z.parse::<i32>()) else { return Err(bad()) };
+        let mut key = key.chars();
+        let (Some(key), None) = (key.next(), key.next()) else { return Err(bad()) };
+        let (x, y, z) = (x as f32, y as f32, z as f32);
+        blocks.push(if key == 'g' { Cube::grass_block(x, y, z) } else { Cube::new(x, y, z, key) });
+    }
+
+    // one relight and BVH build for the whole file, not one per block
+    let old: Vec<ObjectId> = scene.objects().filter(|(_, obj)| block_cell(obj).is_some()).map(|(id, _)| id).collect();
+    Ok(scene.replace_many(old, blocks.into_iter().map(Object::Cube)).len())
+}
+
+// Cell of a unit block
+fn block_cell(obj: &Object) -> Option<[i32; 3]> {
+    let Object::Cube(c) = obj else { return None };
+    unit_cell(c.min, c.max).map(|(x, y, z)| [x, y, z])
+}
+
+// Key `load_blocks` rebuilds a block from: its texture, or `g` for grass blocks
+fn block_key(obj: &Object) -> Option<char> {
+    let Object::Cube(c) = obj else { return None };
+    if c.face_textures[2] == Some('g') { Some('g') } else { c.face_textures[0] }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::textures::TextureManager;
+
+    fn blocks(scene: &Scene) -> Vec<([i32; 3], char)> {
+        let mut blocks: Vec<_> = scene.objects().filter_map(|(_, obj)| block_cell(obj).zip(block_key(obj))).collect();
+        blocks.sort();
+        blocks
+    }
+
+    fn temp_file(name: &str) -> String {
+        std::env::temp_dir().join(format!("raytracing-{}-{}", std::process::id(), name)).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn load_blocks_round_trips_save_blocks() {
+        let objects = vec![
+            Object::Cube(Cube::new(0.0, 0.0, 0.0, '+')),
+            Object::Cube(Cube::grass_block(1.0, 0.0, 0.0)),
+            Object::Cube(Cube::new(-2.0, 3.0, 5.0, 'l')),
+            Object::Cube(Cube::new(0.0, 1.0, 0.0, 't')),
+        ];
+        let saved = Scene::new(objects, Vec::new(), TextureManager::empty());
+        let path = temp_file("round_trip.txt");
+        save_blocks(&saved, &path).unwrap();
+
+        // the loaded blocks replace whatever blocks were there
+        let mut loaded = Scene::new(vec![Object::Cube(Cube::new(9.0, 9.0, 9.0, '-'))], Vec::new(), TextureManager::empty());
+        let count = load_blocks(&mut loaded, &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(count, 4);
+        assert_eq!(blocks(&loaded), blocks(&saved));
+    }
+
+    #[test]
+    fn load_blocks_rejects_malformed_lines() {
+        let path = temp_file("malformed.txt");
+        std::fs::write(&path, "# x y z block\n\n0 0 0 +\n1 2 stone\n").unwrap();
+        let mut scene = Scene::new(Vec::new(), Vec::new(), TextureManager::empty());
+        let err = load_blocks(&mut scene, &path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().ends_with(":4: expected `x y z block`"));
+        assert!(scene.is_empty()); // nothing is loaded from a bad file
+    }
+}
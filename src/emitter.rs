@@ -0,0 +1,383 @@
+// emitter.rs
+use std::ops::Range;
+
+use raylib::prelude::*;
+use rand::Rng;
+
+use crate::{clouds::value_noise, math::orthonormal_basis};
+
+// -------- Emisores de partículas --------
+
+/// Where new particles appear.
+#[derive(Clone, Debug)]
+pub enum EmitterShape {
+    Point(Vector3),
+    Box { min: Vector3, max: Vector3 },
+    Sphere { center: Vector3, radius: f32, shell: bool }, // shell = only on the surface
+    MeshSurface { triangles: Vec<[Vector3; 3]>, cdf: Vec<f32> }, // cdf: cumulative area, for uniform sampling
+}
+
+impl EmitterShape {
+    /// Emits uniformly over the area of `triangles` (e.g. `Object::surface_triangles`).
+    pub fn mesh_surface(triangles: Vec<[Vector3; 3]>) -> Self {
+        let mut total = 0.0;
+        let cdf = triangles
+            .iter()
+            .map(|[a, b, c]| {
+                total += (*b - *a).cross(*c - *a).length() * 0.5;
+                total
+            })
+            .collect();
+        EmitterShape::MeshSurface { triangles, cdf }
+    }
+
+    pub fn center(&self) -> Vector3 {
+        match self {
+            EmitterShape::Point(p) => *p,
+            EmitterShape::Box { min, max } => (*min + *max) * 0.5,
+            EmitterShape::Sphere { center, .. } => *center,
+            EmitterShape::MeshSurface { triangles, .. } => {
+                let sum = triangles.iter().fold(Vector3::zero(), |acc, [a, b, c]| acc + *a + *b + *c);
+                sum / (3.0 * triangles.len().max(1) as f32)
+            }
+        }
+    }
+
+    /// A random spawn point and the outward direction there (used by `VelocityDistribution::Normal`).
+    fn sample(&self, rng: &mut impl Rng) -> (Vector3, Vector3) {
+        match self {
+            EmitterShape::Point(p) => (*p, random_direction(rng)),
+            EmitterShape::Box { min, max } => {
+                let t = Vector3::new(rng.r#gen(), rng.r#gen(), rng.r#gen());
+                (*min + (*max - *min) * t, Vector3::new(0.0, 1.0, 0.0))
+            }
+            EmitterShape::Sphere { center, radius, shell } => {
+                let dir = random_direction(rng);
+                // cube root keeps the volume uniformly filled
+                let r = if *shell { *radius } else { *radius * rng.r#gen::<f32>().cbrt() };
+                (*center + dir * r, dir)
+            }
+            EmitterShape::MeshSurface { triangles, cdf } => {
+                let Some(&total) = cdf.last() else {
+                    return (Vector3::zero(), Vector3::new(0.0, 1.0, 0.0));
+                };
+                let pick = rng.r#gen::<f32>() * total;
+                let i = cdf.partition_point(|&c| c < pick).min(triangles.len() - 1);
+                let [a, b, c] = triangles[i];
+                // uniform barycentrics
+                let (mut u, mut v) = (rng.r#gen::<f32>(), rng.r#gen::<f32>());
+                if u + v > 1.0 {
+                    u = 1.0 - u;
+                    v = 1.0 - v;
+                }
+                let n = (b - a).cross(c - a).normalized();
+                (a + (b - a) * u + (c - a) * v, n)
+            }
+        }
+    }
+}
+
+/// Initial velocity of a new particle.
+#[derive(Clone, Debug)]
+pub enum VelocityDistribution {
+    Uniform { min: Vector3, max: Vector3 },                      // each axis uniform in [min, max]
+    Cone { direction: Vector3, angle: f32, speed: Range<f32> },  // within `angle` radians of `direction`
+    Normal { speed: Range<f32> },                                // along the shape's outward direction
+    Swirl { axis: Vector3, tangential: f32, inward: f32 },      // orbiting the shape's centre
+}
+
+impl VelocityDistribution {
+    fn sample(&self, rng: &mut impl Rng, pos: Vector3, normal: Vector3, center: Vector3) -> Vector3 {
+        match self {
+            VelocityDistribution::Uniform { min, max } => {
+                let t = Vector3::new(rng.r#gen(), rng.r#gen(), rng.r#gen());
+                *min + (*max - *min) * t
+            }
+            VelocityDistribution::Cone { direction, angle, speed } => {
+                let dir = direction.normalized();
+                // uniform over the spherical cap
+                let cos_t = 1.0 - rng.r#gen::<f32>() * (1.0 - angle.cos());
+                let sin_t = (1.0 - cos_t * cos_t).max(0.0).sqrt();
+                let phi = rng.r#gen::<f32>() * std::f32::consts::TAU;
+                let (t, b) = orthonormal_basis(dir);
+                (dir * cos_t + t * (sin_t * phi.cos()) + b * (sin_t * phi.sin())) * sample_range(rng, speed)
+            }
+            VelocityDistribution::Normal { speed } => normal * sample_range(rng, speed),
+            VelocityDistribution::Swirl { axis, tangential, inward } => {
+                let axis = axis.normalized();
+                let r = pos - center;
+                let radial = r - axis * r.dot(axis);
+                if radial.length() < 1e-5 {
+                    return Vector3::zero();
+                }
+                let radial = radial.normalized();
+                axis.cross(radial) * *tangential - radial * *inward
+            }
+        }
+    }
+}
+
+/// Frames of an animated sprite, e.g. `['7', '6', ..., '0']`.
+#[derive(Clone, Debug)]
+pub struct SpriteSheet {
+    pub frames: Vec<char>,
+    pub fps: f32,          // 0 = play the frames once over the particle's lifetime
+    pub random_start: bool, // start each particle on a random frame
+}
+
+impl SpriteSheet {
+    pub fn single(tex_id: char) -> Self {
+        SpriteSheet { frames: vec![tex_id], fps: 0.0, random_start: false }
+    }
+
+    /// Texture for a particle `age` seconds old; `phase` in [0,1) picks its start frame.
+    pub fn frame(&self, age: f32, lifetime: f32, phase: f32) -> char {
+        let n = self.frames.len();
+        if n == 0 {
+            return '#';
+        }
+        let start = if self.random_start { (phase * n as f32) as usize } else { 0 };
+        let i = if self.fps > 0.0 {
+            start + (age * self.fps) as usize
+        } else {
+            start + ((age / lifetime.max(1e-3)) * n as f32) as usize
+        };
+        if self.fps > 0.0 || self.random_start { self.frames[i % n] } else { self.frames[i.min(n - 1)] }
+    }
+}
+
+/// What a particle does when its motion crosses scene geometry.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Collision {
+    None,                                       // flies through everything
+    Bounce { restitution: f32, friction: f32 }, // restitution scales the normal speed, friction the tangential one
+    Stick,                                      // stays where it landed until it dies
+    Die,                                        // disappears on contact
+}
+
+pub struct Emitter {
+    pub name: &'static str,
+    pub enabled: bool,
+    pub shape: EmitterShape,
+    pub rate: f32,            // particles per second
+    pub max_particles: usize, // live cap for this emitter
+    pub lifetime: Range<f32>, // seconds
+    pub velocity: VelocityDistribution,
+
+    // Forces
+    pub gravity: Vector3,       // constant acceleration (positive y = buoyant)
+    pub drag: f32,              // 1/s; velocity decays as e^(-drag·t)
+    pub turbulence: f32,        // strength of the noise acceleration
+    pub turbulence_scale: f32,  // noise frequency, 1/world units
+
+    // Look over the lifetime: keys are (t in [0,1], value), linearly interpolated
+    pub size: Range<f32>,                 // base size in world units
+    pub size_over_life: Vec<(f32, f32)>,  // multiplies the base size
+    pub color_over_life: Vec<(f32, Color)>,
+    pub sheet: SpriteSheet,
+    pub emission: f32, // glow when raytraced; 0 = lit like a surface and casts no light
+
+    pub bounds: Option<(Vector3, Vector3)>, // particles leaving this box die early
+    pub collision: Collision,
+
+    spawn_debt: f32, // fractional particles carried to the next update
+}
+
+impl Emitter {
+    pub fn new(name: &'static str, shape: EmitterShape, rate: f32) -> Self {
+        Emitter {
+            name,
+            enabled: true,
+            shape,
+            rate,
+            max_particles: 64,
+            lifetime: 1.0..4.0,
+            velocity: VelocityDistribution::Uniform { min: Vector3::one() * -0.5, max: Vector3::one() * 0.5 },
+            gravity: Vector3::zero(),
+            drag: 0.0,
+            turbulence: 0.0,
+            turbulence_scale: 1.0,
+            size: 0.2..0.2,
+            size_over_life: vec![(0.0, 1.0)],
+            color_over_life: vec![(0.0, Color::WHITE)],
+            sheet: SpriteSheet::single('0'),
+            emission: 0.0,
+            bounds: None,
+            collision: Collision::None,
+            spawn_debt: 0.0,
+        }
+    }
+
+    /// How many particles to spawn this step (rate × dt, keeping the remainder), given `live` alive.
+    pub fn spawn_count(&mut self, dt: f32, live: usize) -> usize {
+        if !self.enabled {
+            self.spawn_debt = 0.0;
+            return 0;
+        }
+        self.spawn_debt += self.rate * dt;
+        let n = self.spawn_debt.floor();
+        self.spawn_debt -= n;
+        (n as usize).min(self.max_particles.saturating_sub(live))
+    }
+
+    /// Position, velocity, lifetime, base size and a random phase for a new particle.
+    pub fn spawn(&self, rng: &mut impl Rng) -> (Vector3, Vector3, f32, f32, f32) {
+        let (pos, normal) = self.shape.sample(rng);
+        let vel = self.velocity.sample(rng, pos, normal, self.shape.center());
+        (pos, vel, sample_range(rng, &self.lifetime), sample_range(rng, &self.size), rng.r#gen())
+    }
+
+    /// Acceleration on a particle at `p`; `phase` decorrelates particles sharing a spot.
+    pub fn acceleration(&self, p: Vector3, time: f32, phase: f32) -> Vector3 {
+        let mut a = self.gravity;
+        if self.turbulence > 0.0 {
+            let q = p * self.turbulence_scale + Vector3::new(phase * 7.0, time * 0.5, phase * 3.0);
+            // three decorrelated noise lookups, mapped to [-1, 1]
+            let n = Vector3::new(
+                value_noise(q),
+                value_noise(q + Vector3::new(31.7, 0.0, 0.0)),
+                value_noise(q + Vector3::new(0.0, 0.0, 57.3)),
+            );
+            a += (n * 2.0 - Vector3::one()) * self.turbulence;
+        }
+        a
+    }
+
+    pub fn out_of_bounds(&self, p: Vector3) -> bool {
+        self.bounds.is_some_and(|(min, max)| {
+            p.x < min.x || p.y < min.y || p.z < min.z || p.x > max.x || p.y > max.y || p.z > max.z
+        })
+    }
+
+    pub fn size_at(&self, t: f32) -> f32 {
+        curve_at(&self.size_over_life, t, 1.0, |a, b, s| a + (b - a) * s)
+    }
+
+    pub fn color_at(&self, t: f32) -> Color {
+        curve_at(&self.color_over_life, t, Color::WHITE, |a, b, s| {
+            let mix = |x: u8, y: u8| (x as f32 + (y as f32 - x as f32) * s).round() as u8;
+            Color::new(mix(a.r, b.r), mix(a.g, b.g), mix(a.b, b.b), mix(a.a, b.a))
+        })
+    }
+}
+
+// -------- Presets del diorama --------
+
+/// Purple motes swirling around the nether portal (the original sprites).
+pub fn portal_swirl() -> Emitter {
+    let min = Vector3::new(1.0, 0.0, -1.0);
+    let max = Vector3::new(2.0, 3.0, 1.0);
+    Emitter {
+        max_particles: 40,
+        lifetime: 1.0..4.0,
+        velocity: VelocityDistribution::Swirl { axis: Vector3::new(0.0, 0.0, 1.0), tangential: 0.6, inward: 0.15 },
+        turbulence: 0.8,
+        turbulence_scale: 1.5,
+        size: 0.15..0.25,
+        size_over_life: vec![(0.0, 0.4), (0.2, 1.0), (1.0, 0.6)],
+        color_over_life: vec![(0.0, Color::new(200, 110, 255, 255)), (1.0, Color::new(120, 40, 220, 255))],
+        sheet: SpriteSheet { frames: ('0'..='7').rev().collect(), fps: 0.0, random_start: false },
+        emission: 1.0,
+        bounds: Some((min, max)),
+        ..Emitter::new("portal swirl", EmitterShape::Box { min, max }, 10.0)
+    }
+}
+
+/// Grey puffs rising from the top of a lamp block.
+pub fn torch_smoke(top: Vector3) -> Emitter {
+    Emitter {
+        max_particles: 24,
+        lifetime: 2.0..3.5,
+        velocity: VelocityDistribution::Cone { direction: Vector3::new(0.0, 1.0, 0.0), angle: 0.3, speed: 0.3..0.6 },
+        gravity: Vector3::new(0.0, 0.4, 0.0), // hot smoke is buoyant
+        drag: 0.8,
+        turbulence: 0.6,
+        size: 0.12..0.18,
+        size_over_life: vec![(0.0, 1.0), (1.0, 3.5)],
+        color_over_life: vec![(0.0, Color::new(90, 90, 90, 200)), (1.0, Color::new(160, 160, 160, 0))],
+        sheet: SpriteSheet { frames: ('0'..='7').collect(), fps: 0.0, random_start: false },
+        ..Emitter::new("torch smoke", EmitterShape::Sphere { center: top, radius: 0.15, shell: false }, 6.0)
+    }
+}
+
+/// Leaves drifting down over the diorama.
+pub fn falling_leaves() -> Emitter {
+    let min = Vector3::new(-6.0, 5.0, -4.0);
+    let max = Vector3::new(6.0, 5.5, 4.0);
+    Emitter {
+        max_particles: 30,
+        lifetime: 5.0..8.0,
+        velocity: VelocityDistribution::Uniform { min: Vector3::new(-0.3, -0.2, -0.3), max: Vector3::new(0.3, 0.0, 0.3) },
+        gravity: Vector3::new(0.0, -0.8, 0.0),
+        drag: 1.5, // terminal velocity ~0.5 u/s
+        turbulence: 1.5,
+        turbulence_scale: 0.7,
+        size: 0.12..0.2,
+        color_over_life: vec![(0.0, Color::new(120, 200, 80, 255)), (0.6, Color::new(220, 170, 60, 255)), (1.0, Color::new(190, 90, 40, 255))],
+        sheet: SpriteSheet::single('L'),
+        bounds: Some((Vector3::new(-8.0, -1.0, -6.0), Vector3::new(8.0, 6.0, 6.0))),
+        collision: Collision::Stick, // they settle on the blocks
+        ..Emitter::new("falling leaves", EmitterShape::Box { min, max }, 4.0)
+    }
+}
+
+/// Fast streaks of rain (off by default).
+pub fn rain() -> Emitter {
+    let min = Vector3::new(-8.0, 9.0, -6.0);
+    let max = Vector3::new(8.0, 9.5, 6.0);
+    Emitter {
+        enabled: false,
+        max_particles: 400,
+        lifetime: 1.0..1.5,
+        velocity: VelocityDistribution::Cone { direction: Vector3::new(0.1, -1.0, 0.0), angle: 0.05, speed: 7.0..9.0 },
+        gravity: Vector3::new(0.0, -9.8, 0.0),
+        drag: 0.3,
+        size: 0.05..0.08,
+        color_over_life: vec![(0.0, Color::new(170, 200, 255, 180))],
+        sheet: SpriteSheet::single('7'),
+        bounds: Some((Vector3::new(-9.0, -1.0, -7.0), Vector3::new(9.0, 10.0, 7.0))),
+        collision: Collision::Die,
+        ..Emitter::new("rain", EmitterShape::Box { min, max }, 300.0)
+    }
+}
+
+/// Emitters of the sample scene: portal swirl, smoke over both lamps, leaves and rain.
+pub fn diorama_emitters() -> Vec<Emitter> {
+    vec![
+        portal_swirl(),
+        torch_smoke(Vector3::new(-2.5, 2.0, 0.5)),
+        torch_smoke(Vector3::new(4.5, 2.0, -0.5)),
+        falling_leaves(),
+        rain(),
+    ]
+}
+
+// -------- Helpers --------
+
+fn sample_range(rng: &mut impl Rng, r: &Range<f32>) -> f32 {
+    r.start + (r.end - r.start) * rng.r#gen::<f32>()
+}
+
+fn random_direction(rng: &mut impl Rng) -> Vector3 {
+    let z = rng.r#gen::<f32>() * 2.0 - 1.0;
+    let phi = rng.r#gen::<f32>() * std::f32::consts::TAU;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    Vector3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+// Piecewise-linear keys sorted by t; before the first / after the last key the value is held
+fn curve_at<T: Copy>(keys: &[(f32, T)], t: f32, default: T, lerp: impl Fn(T, T, f32) -> T) -> T {
+    let Some(&(first_t, first)) = keys.first() else {
+        return default;
+    };
+    if t <= first_t {
+        return first;
+    }
+    for w in keys.windows(2) {
+        let ((t0, a), (t1, b)) = (w[0], w[1]);
+        if t <= t1 {
+            return lerp(a, b, (t - t0) / (t1 - t0).max(1e-6));
+        }
+    }
+    keys[keys.len() - 1].1
+}
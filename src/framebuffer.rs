@@ -1,44 +1,100 @@
 use raylib::prelude::*;
+use rayon::prelude::*;
+
+use crate::color::linear_to_srgb;
+use crate::image_io::{ExrPixelType, rgb_channels, write_exr, write_pfm_rgb};
+
 pub struct Framebuffer {
     pub width: u32,
     pub height: u32,
-    pub color_buffer: Image,
+    pub pixels: Vec<Color>,   // row-major RGBA8, what gets presented
+    pub depth: Vec<f32>,      // view depth of the traced scene (same units as `Camera::project`), INFINITY = sky
+    pub linear: Vec<Vector3>, // last traced frame in linear float, before clamping to 8 bits
     background_color: Color,
     current_color: Color,
+    #[cfg(feature = "viewer")]
+    texture: Option<Texture2D>, // GPU copy, created on the first present and updated in place
 }
 
 impl Framebuffer {
     pub fn new(width: u32, height: u32, background_color: Color) -> Self {
-        let color_buffer = Image::gen_image_color(width as i32, height as i32, background_color);
+        let n = (width * height) as usize;
         Framebuffer {
             width,
             height,
-            color_buffer,
+            pixels: vec![background_color; n],
+            depth: vec![f32::INFINITY; n],
+            linear: vec![Vector3::zero(); n],
             background_color,
             current_color: Color::WHITE,
+            #[cfg(feature = "viewer")]
+            texture: None,
         }
     }
 
-    /// Clears the framebuffer by regenerating the color buffer with the background color
+    /// Reallocates the buffers for a new size (window resize, dynamic resolution).
+    /// The contents are cleared; the GPU texture is recreated on the next present.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if (width, height) == (self.width, self.height) {
+            return;
+        }
+        let n = (width * height) as usize;
+        self.width = width;
+        self.height = height;
+        self.pixels.clear();
+        self.pixels.resize(n, self.background_color);
+        self.depth.clear();
+        self.depth.resize(n, f32::INFINITY);
+        self.linear.clear();
+        self.linear.resize(n, Vector3::zero());
+        #[cfg(feature = "viewer")]
+        {
+            self.texture = None;
+        }
+    }
+
+    /// Clears color to the background and depth to infinity, without reallocating
     pub fn clear(&mut self) {
-        self.color_buffer = Image::gen_image_color(self.width as i32, self.height as i32, self.background_color);
+        self.pixels.fill(self.background_color);
+        self.depth.fill(f32::INFINITY);
+    }
+
+    fn index(&self, x: u32, y: u32) -> Option<usize> {
+        if x < self.width && y < self.height {
+            Some((y * self.width + x) as usize)
+        } else {
+            None
+        }
     }
 
     /// Sets a single pixel in the buffer to the current color, if within bounds
     pub fn set_pixel(&mut self, x: u32, y: u32) {
-        if x < self.width && y < self.height {
-            // Calculate the offset into the Image data (raylib stores pixels in row-major order)
-            self.color_buffer.draw_pixel(x as i32, y as i32, self.current_color);
-            //let offset = (y * self.width + x) as usize;
-            // Safety: data is a contiguous Vec<u8> RGBA, but raylib-rs Image uses Color per u32
-            //unsafe {
-                //let ptr = self.color_buffer.data().as_mut_ptr() as *mut Color;
-                //*ptr.add(offset) = self.current_color;
-            //}
+        if let Some(i) = self.index(x, y) {
+            self.pixels[i] = self.current_color;
+        }
+    }
+
+    /// Alpha-blends `color` over the pixel: result = color * alpha + pixel * (1 - alpha)
+    pub fn blend_pixel(&mut self, x: u32, y: u32, color: Color, alpha: f32) {
+        if let Some(i) = self.index(x, y) {
+            let a = alpha.clamp(0.0, 1.0);
+            let dst = self.pixels[i];
+            let mix = |s: u8, d: u8| (s as f32 * a + d as f32 * (1.0 - a)).round() as u8;
+            self.pixels[i] = Color::new(mix(color.r, dst.r), mix(color.g, dst.g), mix(color.b, dst.b), dst.a);
         }
     }
-    pub fn get_color(&mut self, x: u32, y: u32) {
-        self.color_buffer.get_color(x as i32, y as i32);
+
+    pub fn get_color(&self, x: u32, y: u32) -> Color {
+        self.index(x, y).map_or(self.background_color, |i| self.pixels[i])
+    }
+
+    pub fn depth_at(&self, x: u32, y: u32) -> f32 {
+        self.index(x, y).map_or(f32::INFINITY, |i| self.depth[i])
+    }
+
+    /// True if something at `depth` would be in front of the traced scene at (x, y).
+    pub fn depth_test(&self, x: u32, y: u32, depth: f32) -> bool {
+        depth < self.depth_at(x, y)
     }
 
     pub fn set_background_color(&mut self, color: Color) {
@@ -49,19 +105,71 @@ impl Framebuffer {
         self.current_color = color;
     }
 
+    /// Converts the linear float frame into the 8-bit pixels (in parallel).
+    pub fn resolve(&mut self) {
+        self.pixels
+            .par_iter_mut()
+            .zip(self.linear.par_iter())
+            .for_each(|(p, c)| *p = linear_to_srgb(c.x, c.y, c.z));
+    }
+
+    fn to_image(&self) -> Image {
+        let mut image = Image::gen_image_color(self.width as i32, self.height as i32, self.background_color);
+        for (i, c) in self.pixels.iter().enumerate() {
+            let (x, y) = (i as u32 % self.width, i as u32 / self.width);
+            image.draw_pixel(x as i32, y as i32, *c);
+        }
+        image
+    }
+
     /// Exports the framebuffer to an image file (BMP/PNG/etc.) using raylib's FFI
     pub fn render_to_file(&self, file_path: &str) {
-        self.color_buffer.export_image(file_path);
+        self.to_image().export_image(file_path);
+    }
+
+    /// Exports the linear float frame as OpenEXR (half or full float).
+    pub fn render_to_exr(&self, file_path: &str, pixel_type: ExrPixelType) -> std::io::Result<()> {
+        let channels = rgb_channels("", &self.linear);
+        write_exr(file_path, self.width as usize, self.height as usize, &channels, pixel_type)
+    }
+
+    /// Exports the linear float frame as PFM (portable float map).
+    pub fn render_to_pfm(&self, file_path: &str) -> std::io::Result<()> {
+        write_pfm_rgb(file_path, self.width as usize, self.height as usize, &self.linear)
     }
 
+    /// Uploads the pixels with a single texture update and draws them.
+    #[cfg(feature = "viewer")]
     pub fn swap_buffers(
-        &self,
+        &mut self,
         window: &mut RaylibHandle,
         raylib_thread: &RaylibThread,
     ) {
-        if let Ok(texture) = window.load_texture_from_image(raylib_thread, &self.color_buffer) {
-            let mut renderer = window.begin_drawing(raylib_thread);
-            renderer.draw_texture(&texture, 0, 0, Color::WHITE);
+        self.swap_buffers_with(window, raylib_thread, |_| {});
+    }
+
+    /// Like `swap_buffers`, then `overlay` draws on top (HUD, hotbar) in the same frame.
+    /// The overlay only touches the window, never the traced pixels.
+    #[cfg(feature = "viewer")]
+    pub fn swap_buffers_with(
+        &mut self,
+        window: &mut RaylibHandle,
+        raylib_thread: &RaylibThread,
+        overlay: impl FnOnce(&mut RaylibDrawHandle),
+    ) {
+        if self.texture.is_none() {
+            self.texture = window.load_texture_from_image(raylib_thread, &self.to_image()).ok();
+        }
+        let Some(texture) = self.texture.as_mut() else {
+            return;
+        };
+        let bytes: Vec<u8> = self.pixels.iter().flat_map(|c| [c.r, c.g, c.b, c.a]).collect();
+        if texture.update_texture(&bytes).is_err() {
+            return;
         }
+
+        let mut renderer = window.begin_drawing(raylib_thread);
+        renderer.draw_texture(&*texture, 0, 0, Color::WHITE);
+        overlay(&mut renderer);
     }
-}
\ No newline at end of file
+}
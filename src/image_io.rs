@@ -0,0 +1,222 @@
+// image_io.rs
+use std::io::{self, BufWriter, Write};
+
+use raylib::prelude::*;
+
+// -------- Imágenes float: OpenEXR y PFM --------
+
+/// One channel of a float image, e.g. `("R", data)` or `("normal.X", data)`; one value per pixel.
+pub struct Channel {
+    pub name: String,
+    pub data: Vec<f32>,
+}
+
+impl Channel {
+    pub fn new(name: &str, data: Vec<f32>) -> Self {
+        Channel { name: name.to_string(), data }
+    }
+}
+
+/// Splits an RGB buffer into `<prefix>R`, `<prefix>G`, `<prefix>B` channels.
+pub fn rgb_channels(prefix: &str, pixels: &[Vector3]) -> Vec<Channel> {
+    vec![
+        Channel::new(&format!("{}R", prefix), pixels.iter().map(|p| p.x).collect()),
+        Channel::new(&format!("{}G", prefix), pixels.iter().map(|p| p.y).collect()),
+        Channel::new(&format!("{}B", prefix), pixels.iter().map(|p| p.z).collect()),
+    ]
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExrPixelType {
+    Half,
+    Float,
+}
+
+/// Writes an uncompressed scanline OpenEXR file.
+/// Layers are just channel name prefixes (`depth.Z`, `normal.X`, ...), which compositing tools group.
+pub fn write_exr(path: &str, width: usize, height: usize, channels: &[Channel], pixel_type: ExrPixelType) -> io::Result<()> {
+    // EXR wants channels sorted by name, in the header and in every scanline
+    let mut channels: Vec<&Channel> = channels.iter().collect();
+    channels.sort_by(|a, b| a.name.cmp(&b.name));
+    for c in &channels {
+        if c.data.len() != width * height {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("channel {} has the wrong size", c.name)));
+        }
+    }
+    let long_names = channels.iter().any(|c| c.name.len() > 31);
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&0x0131_2f76_u32.to_le_bytes()); // magic
+    header.extend_from_slice(&(2_u32 | if long_names { 0x400 } else { 0 }).to_le_bytes()); // version 2, scanline
+
+    let mut chlist = Vec::new();
+    for c in &channels {
+        chlist.extend_from_slice(c.name.as_bytes());
+        chlist.push(0);
+        chlist.extend_from_slice(&(if pixel_type == ExrPixelType::Half { 1_i32 } else { 2_i32 }).to_le_bytes());
+        chlist.extend_from_slice(&[0, 0, 0, 0]); // pLinear + reserved
+        chlist.extend_from_slice(&1_i32.to_le_bytes()); // x sampling
+        chlist.extend_from_slice(&1_i32.to_le_bytes()); // y sampling
+    }
+    chlist.push(0);
+    attribute(&mut header, "channels", "chlist", &chlist);
+    attribute(&mut header, "compression", "compression", &[0]); // NO_COMPRESSION
+
+    let mut window = Vec::new();
+    for v in [0, 0, width as i32 - 1, height as i32 - 1] {
+        window.extend_from_slice(&v.to_le_bytes());
+    }
+    attribute(&mut header, "dataWindow", "box2i", &window);
+    attribute(&mut header, "displayWindow", "box2i", &window);
+    attribute(&mut header, "lineOrder", "lineOrder", &[0]); // increasing y
+    attribute(&mut header, "pixelAspectRatio", "float", &1.0_f32.to_le_bytes());
+    attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+    attribute(&mut header, "screenWindowWidth", "float", &1.0_f32.to_le_bytes());
+    header.push(0);
+
+    // One scanline per block: offset table, then (y, size, channel rows...) per line
+    let bytes_per_value = if pixel_type == ExrPixelType::Half { 2 } else { 4 };
+    let line_size = width * channels.len() * bytes_per_value;
+    let first_block = header.len() + height * 8;
+
+    let mut out = BufWriter::new(std::fs::File::create(path)?);
+    out.write_all(&header)?;
+    for y in 0..height {
+        out.write_all(&((first_block + y * (8 + line_size)) as u64).to_le_bytes())?;
+    }
+    for y in 0..height {
+        out.write_all(&(y as i32).to_le_bytes())?;
+        out.write_all(&(line_size as i32).to_le_bytes())?;
+        for c in &channels {
+            for &v in &c.data[y * width..(y + 1) * width] {
+                match pixel_type {
+                    ExrPixelType::Half => out.write_all(&f32_to_f16(v).to_le_bytes())?,
+                    ExrPixelType::Float => out.write_all(&v.to_le_bytes())?,
+                }
+            }
+        }
+    }
+    out.flush()
+}
+
+fn attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(kind.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+/// IEEE half from f32, round to nearest; overflows become infinity.
+pub fn f32_to_f16(v: f32) -> u16 {
+    let x = v.to_bits();
+    let sign = ((x >> 16) & 0x8000) as u16;
+    let exp = ((x >> 23) & 0xff) as i32;
+    let mant = x & 0x007f_ffff;
+
+    if exp == 255 {
+        // inf / NaN (keep NaN a NaN)
+        return sign | 0x7c00 | if mant != 0 { 0x0200 } else { 0 };
+    }
+    let e = exp - 127 + 15;
+    if e >= 31 {
+        return sign | 0x7c00;
+    }
+    if e <= 0 {
+        // subnormal half (or zero)
+        if e < -10 {
+            return sign;
+        }
+        let m = (mant | 0x0080_0000) >> (1 - e);
+        return sign | ((m + 0x1000) >> 13) as u16;
+    }
+    let half = ((e as u32) << 10) | (mant >> 13);
+    let round = (mant >> 12) & 1; // a carry into the exponent is still the right answer
+    sign | (half + round) as u16
+}
+
+/// Writes a Portable Float Map: `PF` (RGB) for 3 channels, `Pf` (grayscale) for 1.
+/// `values` has `channels` floats per pixel, rows top to bottom.
+pub fn write_pfm(path: &str, width: usize, height: usize, channels: usize, values: &[f32]) -> io::Result<()> {
+    if channels != 1 && channels != 3 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "PFM only stores 1 or 3 channels"));
+    }
+    let mut out = BufWriter::new(std::fs::File::create(path)?);
+    writeln!(out, "{}", if channels == 3 { "PF" } else { "Pf" })?;
+    writeln!(out, "{} {}", width, height)?;
+    writeln!(out, "-1.0")?; // negative scale = little endian
+
+    // PFM stores the bottom row first
+    let row = width * channels;
+    for y in (0..height).rev() {
+        for v in &values[y * row..(y + 1) * row] {
+            out.write_all(&v.to_le_bytes())?;
+        }
+    }
+    out.flush()
+}
+
+pub fn write_pfm_rgb(path: &str, width: usize, height: usize, pixels: &[Vector3]) -> io::Result<()> {
+    let values: Vec<f32> = pixels.iter().flat_map(|p| [p.x, p.y, p.z]).collect();
+    write_pfm(path, width, height, 3, &values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Reference decoder for the round trip
+    fn f16_to_f32(h: u16) -> f32 {
+        let sign = if h & 0x8000 != 0 { -1.0 } else { 1.0 };
+        let exp = ((h >> 10) & 0x1f) as i32;
+        let mant = (h & 0x3ff) as f32;
+        match exp {
+            0 => sign * mant * 2f32.powi(-24),
+            31 if mant == 0.0 => sign * f32::INFINITY,
+            31 => f32::NAN,
+            _ => sign * (1.0 + mant / 1024.0) * 2f32.powi(exp - 15),
+        }
+    }
+
+    #[test]
+    fn f32_to_f16_known_values() {
+        assert_eq!(f32_to_f16(0.0), 0x0000);
+        assert_eq!(f32_to_f16(-0.0), 0x8000);
+        assert_eq!(f32_to_f16(1.0), 0x3c00);
+        assert_eq!(f32_to_f16(-2.0), 0xc000);
+        assert_eq!(f32_to_f16(0.5), 0x3800);
+        assert_eq!(f32_to_f16(65504.0), 0x7bff); // largest half
+        assert_eq!(f32_to_f16(2f32.powi(-14)), 0x0400); // smallest normal
+        assert_eq!(f32_to_f16(2f32.powi(-24)), 0x0001); // smallest subnormal
+        assert_eq!(f32_to_f16(2f32.powi(-30)), 0x0000); // underflows to zero
+    }
+
+    #[test]
+    fn f32_to_f16_overflow_and_specials() {
+        assert_eq!(f32_to_f16(1.0e6), 0x7c00);
+        assert_eq!(f32_to_f16(65520.0), 0x7c00); // rounds up past the largest half
+        assert_eq!(f32_to_f16(f32::INFINITY), 0x7c00);
+        assert_eq!(f32_to_f16(f32::NEG_INFINITY), 0xfc00);
+        let nan = f32_to_f16(f32::NAN);
+        assert_eq!(nan & 0x7c00, 0x7c00);
+        assert_ne!(nan & 0x03ff, 0);
+    }
+
+    #[test]
+    fn f32_to_f16_rounds_to_nearest() {
+        assert_eq!(f32_to_f16(1.0 + 2f32.powi(-10)), 0x3c01); // exactly one step up
+        assert_eq!(f32_to_f16(1.0 + 0.3 * 2f32.powi(-10)), 0x3c00);
+        assert_eq!(f32_to_f16(1.0 + 0.7 * 2f32.powi(-10)), 0x3c01);
+    }
+
+    #[test]
+    fn f32_to_f16_round_trips_every_finite_half() {
+        for h in 0..=u16::MAX {
+            if h & 0x7c00 == 0x7c00 {
+                continue; // inf / NaN
+            }
+            assert_eq!(f32_to_f16(f16_to_f32(h)), h, "half {:#06x}", h);
+        }
+    }
+}
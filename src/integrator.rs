@@ -0,0 +1,345 @@
+// integrator.rs
+use rand::Rng;
+use raylib::prelude::*;
+
+use crate::{
+    aov::AovSample,
+    color::srgb_to_linear,
+    math::*,
+    media::apply_media,
+    occlusion::ambient_occlusion,
+    ray_intersect::Hit,
+    renderer::{cast_ray, direct_lighting, particle_glow, surface_color},
+    scene::{Scene, intersection_tests, reset_intersection_tests},
+};
+
+// -------- Integradores: qué color devuelve un rayo --------
+
+/// Turns a camera ray into a (linear) color. The renderer calls `li` once per sample.
+pub trait Integrator: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Color seen along the ray. With `aov`, the first hit's passes are written there.
+    fn li(&self, ro: &Vector3, rd: &Vector3, scene: &Scene, time: f32, aov: Option<&mut AovSample>) -> Vector3;
+
+    /// Whether the denoiser should run on this integrator's output (debug views want raw values).
+    fn denoise(&self) -> bool {
+        true
+    }
+}
+
+/// Names accepted by `integrator_by_name`, in the order the viewer cycles through them.
+pub const INTEGRATORS: [&str; 7] = ["whitted", "path", "ao", "normals", "uv", "albedo", "heatmap"];
+
+pub fn integrator_by_name(name: &str) -> Option<Box<dyn Integrator>> {
+    Some(match name {
+        "whitted" => Box::new(Whitted),
+        "path" => Box::new(PathTracer::new()),
+        "ao" => Box::new(AmbientOcclusion::new()),
+        "normals" => Box::new(DebugView::Normals),
+        "uv" => Box::new(DebugView::Uv),
+        "albedo" => Box::new(DebugView::Albedo),
+        "heatmap" => Box::new(Heatmap::new()),
+        _ => return None,
+    })
+}
+
+/// The integrator after `current` in `INTEGRATORS` (wrapping around).
+pub fn next_integrator(current: &dyn Integrator) -> Box<dyn Integrator> {
+    let i = INTEGRATORS.iter().position(|n| *n == current.name()).map_or(0, |i| (i + 1) % INTEGRATORS.len());
+    integrator_by_name(INTEGRATORS[i]).expect("every name in INTEGRATORS is known")
+}
+
+// Passes of a first hit that don't depend on how it gets shaded
+fn hit_sample(hit: &Hit, base: Vector3) -> AovSample {
+    let m = hit.material;
+    let (er, eg, eb) = srgb_to_linear(m.emission);
+    AovSample {
+        depth: hit.distance,
+        position: hit.point,
+        normal: hit.normal,
+        albedo: base,
+        obj_id: Some(hit.obj_id),
+        material_id: m.id(),
+        uv: hit.uv,
+        direct: Vector3::zero(),
+        indirect: Vector3::zero(),
+        emission: Vector3::new(er, eg, eb) * m.emission_strength,
+        shadow: 0.0,
+    }
+}
+
+fn base_color(hit: &Hit, scene: &Scene) -> Vector3 {
+    let (r, g, b) = srgb_to_linear(surface_color(hit, &scene.textures, &scene.sky));
+    Vector3::new(r, g, b)
+}
+
+// -------- Whitted --------
+
+/// The classic shading: ambient + Lambert/Phong from every light, mirror reflection and refraction.
+pub struct Whitted;
+
+impl Integrator for Whitted {
+    fn name(&self) -> &'static str {
+        "whitted"
+    }
+
+    fn li(&self, ro: &Vector3, rd: &Vector3, scene: &Scene, time: f32, aov: Option<&mut AovSample>) -> Vector3 {
+        cast_ray(ro, rd, scene, 0, time, aov)
+    }
+}
+
+// -------- Path tracer --------
+
+/// Monte Carlo path tracer: direct light from every light at each bounce, then one random lobe
+/// (diffuse, reflection or refraction) picked by its weight. The sky lights the scene through
+/// the diffuse bounces instead of the constant ambient term.
+pub struct PathTracer {
+    pub samples: u32,     // paths per camera sample
+    pub max_bounces: u32,
+    pub rr_depth: u32,    // Russian roulette from this bounce on
+}
+
+impl PathTracer {
+    pub fn new() -> Self {
+        PathTracer { samples: 4, max_bounces: 4, rr_depth: 2 }
+    }
+
+    fn trace(
+        &self,
+        ro: &Vector3,
+        rd: &Vector3,
+        scene: &Scene,
+        bounce: Bounce,
+        time: f32,
+        aov: Option<&mut AovSample>,
+    ) -> Vector3 {
+        let depth = bounce.depth;
+        let closest = scene.intersect(ro, rd, time);
+        if !closest.is_intersecting {
+            // after a diffuse bounce the sun and moon were already sampled as lights
+            let sky = if bounce.diffuse { scene.sky.procedural_sky_without_discs(*rd) } else { scene.sky.procedural_sky(*rd) };
+            return apply_media(sky, ro, rd, f32::INFINITY, scene, time);
+        }
+
+        let m = closest.material;
+        let base = base_color(&closest, scene);
+        let (light, shadow_total, shadow_blocked) = direct_lighting(&closest, ro, base, scene, time);
+
+        // Same lobe weights as the Whitted shading
+        let cos_theta = (-rd.dot(closest.normal)).max(0.0);
+        let kr = fresnel_schlick(cos_theta, m.reflectivity.clamp(0.0, 1.0));
+        let kt = m.transparency.clamp(0.0, 1.0);
+        let kd = (1.0 - kr - kt).max(0.0);
+        let direct = (light + particle_glow(&closest, base, scene)) * kd;
+
+        let mut indirect = Vector3::zero();
+        if depth + 1 < self.max_bounces {
+            let mut rng = rand::thread_rng();
+            let diffuse = base * m.albedo;
+            let survive = if depth >= self.rr_depth {
+                (kr + kt + kd * diffuse.x.max(diffuse.y).max(diffuse.z)).clamp(0.05, 0.95)
+            } else {
+                1.0
+            };
+            if rng.r#gen::<f32>() < survive {
+                let eps = 1e-3;
+                let total = kr + kt + kd;
+                let pick = rng.r#gen::<f32>() * total;
+                let refr_dir = if pick >= kr && pick < kr + kt { refract(*rd, closest.normal, 1.0, m.ior) } else { None };
+                let li = if let Some(dir) = refr_dir {
+                    let origin = closest.point - closest.normal * eps; // slightly inside
+                    self.trace(&origin, &dir.normalized(), scene, Bounce::specular(depth + 1), time, None)
+                } else if pick < kr + kt {
+                    // mirror reflection (also taken on total internal reflection)
+                    let dir = reflect(*rd, closest.normal).normalized();
+                    let origin = closest.point + closest.normal * eps;
+                    self.trace(&origin, &dir, scene, Bounce::specular(depth + 1), time, None)
+                } else {
+                    // Lambert: cosine-weighted bounce on the side the ray came from
+                    let n = if rd.dot(closest.normal) > 0.0 { -closest.normal } else { closest.normal };
+                    let dir = cosine_hemisphere(n, rng.r#gen(), rng.r#gen());
+                    let li = self.trace(&(closest.point + n * eps), &dir, scene, Bounce { depth: depth + 1, diffuse: true }, time, None);
+                    Vector3::new(li.x * diffuse.x, li.y * diffuse.y, li.z * diffuse.z)
+                };
+                indirect = li * (total / survive);
+            }
+        }
+
+        if let Some(aov) = aov {
+            *aov = AovSample {
+                direct,
+                indirect,
+                shadow: if shadow_total > 0.0 { shadow_blocked / shadow_total } else { 0.0 },
+                ..hit_sample(&closest, base)
+            };
+        }
+
+        apply_media(direct + indirect, ro, rd, closest.distance, scene, time)
+    }
+}
+
+// How a path got to its current vertex
+#[derive(Clone, Copy, Debug)]
+struct Bounce {
+    depth: u32,
+    diffuse: bool, // through a diffuse bounce, whose vertex already sampled the lights
+}
+
+impl Bounce {
+    // camera rays, mirrors and refraction: the lights were not sampled along the way
+    fn specular(depth: u32) -> Self {
+        Bounce { depth, diffuse: false }
+    }
+}
+
+impl Default for PathTracer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Integrator for PathTracer {
+    fn name(&self) -> &'static str {
+        "path"
+    }
+
+    fn li(&self, ro: &Vector3, rd: &Vector3, scene: &Scene, time: f32, mut aov: Option<&mut AovSample>) -> Vector3 {
+        let n = self.samples.max(1);
+        let mut sum = Vector3::zero();
+        for i in 0..n {
+            let aov = if i == 0 { aov.as_deref_mut() } else { None };
+            sum += self.trace(ro, rd, scene, Bounce::specular(0), time, aov);
+        }
+        sum / n as f32
+    }
+}
+
+// -------- Ambient occlusion --------
+
+/// White where the hemisphere above the first hit is open, dark in creases and corners.
+pub struct AmbientOcclusion {
+    pub radius: f32,
+    pub samples: u32,
+}
+
+impl AmbientOcclusion {
+    pub fn new() -> Self {
+        AmbientOcclusion { radius: 1.5, samples: 16 }
+    }
+}
+
+impl Default for AmbientOcclusion {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Integrator for AmbientOcclusion {
+    fn name(&self) -> &'static str {
+        "ao"
+    }
+
+    fn li(&self, ro: &Vector3, rd: &Vector3, scene: &Scene, time: f32, aov: Option<&mut AovSample>) -> Vector3 {
+        let closest = scene.intersect_objects(ro, rd, time);
+        if !closest.is_intersecting {
+            return Vector3::one();
+        }
+        let n = if rd.dot(closest.normal) > 0.0 { -closest.normal } else { closest.normal };
+        let ao = ambient_occlusion(scene, closest.point, n, self.radius, self.samples, time, &mut rand::thread_rng());
+        if let Some(aov) = aov {
+            *aov = hit_sample(&closest, base_color(&closest, scene));
+        }
+        Vector3::one() * ao
+    }
+}
+
+// -------- Vistas de depuración --------
+
+/// Raw first-hit values: normals mapped to 0..1, UVs as red/green, or the unlit base color.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DebugView {
+    Normals,
+    Uv,
+    Albedo,
+}
+
+impl Integrator for DebugView {
+    fn name(&self) -> &'static str {
+        match self {
+            DebugView::Normals => "normals",
+            DebugView::Uv => "uv",
+            DebugView::Albedo => "albedo",
+        }
+    }
+
+    fn li(&self, ro: &Vector3, rd: &Vector3, scene: &Scene, time: f32, aov: Option<&mut AovSample>) -> Vector3 {
+        let closest = scene.intersect(ro, rd, time);
+        if !closest.is_intersecting {
+            return Vector3::zero();
+        }
+        let base = base_color(&closest, scene);
+        if let Some(aov) = aov {
+            *aov = hit_sample(&closest, base);
+        }
+        match self {
+            DebugView::Normals => (closest.normal + Vector3::one()) * 0.5,
+            DebugView::Uv => Vector3::new(closest.uv.x.fract(), closest.uv.y.fract(), 0.0),
+            DebugView::Albedo => base,
+        }
+    }
+
+    fn denoise(&self) -> bool {
+        false
+    }
+}
+
+// -------- Heatmap --------
+
+/// Shades like `Whitted` but shows how many intersection tests (BVH nodes + objects) each pixel
+/// cost: blue = few, red = `max_tests` or more.
+pub struct Heatmap {
+    pub max_tests: u32,
+}
+
+impl Heatmap {
+    pub fn new() -> Self {
+        Heatmap { max_tests: 512 }
+    }
+}
+
+impl Default for Heatmap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Integrator for Heatmap {
+    fn name(&self) -> &'static str {
+        "heatmap"
+    }
+
+    fn li(&self, ro: &Vector3, rd: &Vector3, scene: &Scene, time: f32, aov: Option<&mut AovSample>) -> Vector3 {
+        reset_intersection_tests();
+        cast_ray(ro, rd, scene, 0, time, aov);
+        heat_color(intersection_tests() as f32 / self.max_tests.max(1) as f32)
+    }
+
+    fn denoise(&self) -> bool {
+        false
+    }
+}
+
+// blue → cyan → green → yellow → red
+fn heat_color(t: f32) -> Vector3 {
+    const STOPS: [Vector3; 5] = [
+        Vector3 { x: 0.0, y: 0.0, z: 1.0 },
+        Vector3 { x: 0.0, y: 1.0, z: 1.0 },
+        Vector3 { x: 0.0, y: 1.0, z: 0.0 },
+        Vector3 { x: 1.0, y: 1.0, z: 0.0 },
+        Vector3 { x: 1.0, y: 0.0, z: 0.0 },
+    ];
+    let x = t.clamp(0.0, 1.0) * (STOPS.len() - 1) as f32;
+    let i = (x as usize).min(STOPS.len() - 2);
+    STOPS[i].lerp(STOPS[i + 1], x - i as f32)
+}
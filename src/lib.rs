@@ -0,0 +1,61 @@
+// lib.rs
+//! CPU raytracer for block dioramas.
+//!
+//! The stable entry points are re-exported at the crate root: build a [`Scene`], point a
+//! [`Camera`] at it and let a [`Renderer`] fill a [`Framebuffer`]. The modules are private;
+//! everything the binaries need is re-exported below. The raylib window, music and keyboard
+//! handling live in the `viewer` binary (cargo feature `viewer`, on by default).
+//!
+//! Turning `viewer` off only drops the window code (presenting a `Framebuffer`, GPU textures).
+//! raylib stays a dependency: its `Vector3`, `Color` and `Image` are used throughout the tracer,
+//! so the raylib C library is still built (cmake and a C compiler are needed either way).
+
+mod framebuffer;
+mod ray_intersect;
+mod bvh;
+mod material;
+mod camera;
+mod textures;
+mod light;
+mod object;
+mod color;
+mod math;
+mod skybox;
+mod sprites;
+mod clouds;
+mod media;
+mod occlusion;
+mod voxel_light;
+mod animation;
+mod motion;
+mod denoise;
+mod aov;
+mod image_io;
+mod emitter;
+mod editor;
+mod scene;
+mod renderer;
+mod resolution;
+mod temporal;
+mod tiles;
+mod integrator;
+
+pub use animation::{CameraPath, FrameClock};
+pub use aov::AovBuffer;
+pub use camera::{Camera, CameraMode, Projection};
+pub use editor::{BlockType, Hotbar, Pick, break_block, load_blocks, place_block, save_blocks};
+pub use emitter::diorama_emitters;
+pub use framebuffer::Framebuffer;
+pub use integrator::{INTEGRATORS, Integrator, integrator_by_name, next_integrator};
+pub use light::PointLight;
+pub use material::Material;
+pub use motion::Motion;
+pub use object::{Cube, MovingObject, Object};
+pub use occlusion::AoMode;
+pub use renderer::{Renderer, export_aovs};
+pub use resolution::{DynamicResolution, Upscale, upscale, upscale_sequential};
+pub use scene::{ObjectId, Scene};
+pub use skybox::Sky;
+pub use sprites::{SpriteSystem, render_sprites};
+pub use textures::TextureManager;
+pub use tiles::{Progress, TileOrder};
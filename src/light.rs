@@ -2,26 +2,31 @@ use std::f32::consts::PI;
 
 use raylib::prelude::*;
 
+use crate::motion::Motion;
 use crate::object::Object;
+use crate::ray_intersect::ObjectId;
+use crate::scene::Scene;
 
 // -------- Luz puntual simple --------
 #[derive(Clone, Copy, Debug)]
 pub struct PointLight {
     pub position: Vector3,
     pub intensity: f32, // escala [0..∞), e.g. 1.0 = normal
-    pub emitter_index: Option<usize>,
+    pub emitter: Option<ObjectId>, // object giving off this light; it doesn't shadow itself
     pub color: Color,
+    pub motion: Motion, // movement while the shutter is open
+    pub radius: f32,    // distance where the light fades out completely (0 = no falloff)
     theta: f32,
 }
 
 impl PointLight {
-    pub fn new(position: Vector3, intensity: f32, color: Color, emitter_index: Option<usize>) -> Self {
+    pub fn new(position: Vector3, intensity: f32, color: Color, emitter: Option<ObjectId>) -> Self {
         let size = position.length();
         let mut theta = (position.x / size).acos();
         if position.y < 0.0 {
             theta = 2.0*PI - theta
         }
-        PointLight { position, intensity, color, emitter_index, theta }
+        PointLight { position, intensity, color, emitter, motion: Motion::Static, radius: 0.0, theta }
     }
     pub fn rotate(&mut self, theta: f32) {
         //let rot_speed = PI / 75.0;
@@ -31,6 +36,21 @@ impl PointLight {
         self.position.y = size * self.theta.sin();
     }
 
+    /// Smooth windowed falloff in [0,1]; always 1 for lights without a radius.
+    pub fn falloff(&self, dist: f32) -> f32 {
+        if self.radius <= 0.0 {
+            return 1.0;
+        }
+        let x = (dist / self.radius).min(1.0);
+        let w = 1.0 - x * x;
+        w * w
+    }
+
+    /// Position at `time` seconds after the shutter opens.
+    pub fn position_at(&self, time: f32) -> Vector3 {
+        self.position + self.motion.offset(time)
+    }
+
     pub fn set_intensity(&mut self, intensity: f32) {
         self.intensity = intensity;
     }
@@ -39,11 +59,11 @@ impl PointLight {
     }
 }
 
-pub fn build_lights_from_objects(objects: &[Object]) -> Vec<PointLight> {
+pub fn build_lights_from_objects<'a>(objects: impl IntoIterator<Item = (ObjectId, &'a Object)>) -> Vec<PointLight> {
     let mut lights = Vec::new();
     
     // Add a point light for each emissive object
-    for (i, obj) in objects.iter().enumerate() {
+    for (i, obj) in objects {
         match obj {
             Object::Sphere(s) => {
                 if s.material.emission_strength > 0.0 {
@@ -81,8 +101,62 @@ pub fn build_lights_from_objects(objects: &[Object]) -> Vec<PointLight> {
                     
                 }
             }
+            Object::Billboards(_) => {
+                // particles change every frame: their lights come from `BillboardCloud::lights`
+            }
+            Object::Moving(m) => {
+                // The light of a moving emitter follows it
+                for mut light in build_lights_from_objects([(i, &*m.object)]) {
+                    light.motion = m.motion;
+                    lights.push(light);
+                }
+            }
         }
     }
 
     lights
-}
\ No newline at end of file
+}
+
+/// Shadow ray with transparency-aware visibility: 1.0 = fully lit, 0.0 = fully blocked.
+pub fn shadow_visibility(
+    origin: &Vector3,
+    l_dir: &Vector3,
+    light_dist: f32,
+    emitter: Option<ObjectId>,
+    scene: &Scene,
+    time: f32,
+) -> f32 {
+    let mut visibility = 1.0;
+    scene.visit(origin, l_dir, light_dist, |id, obj| {
+        if Some(id) == emitter {
+            return true;
+        }
+        let h = obj.ray_intersect_at(origin, l_dir, id, time);
+        if h.is_intersecting && h.distance < light_dist {
+            let mat_blocker = h.material;
+            // If the blocker is transparent, let some light through
+            // With our single-hit intersection we can't gather multiple layers,
+            // so we just attenuate once and stop.
+            visibility = if mat_blocker.transparency > 0.0 {
+                mat_blocker.transparency.clamp(0.0, 1.0)
+            } else {
+                // Opaque blocker: full shadow
+                0.0
+            };
+            return false;
+        }
+        true
+    });
+    if visibility < 1.0 || emitter == Some(ObjectId::PARTICLES) {
+        return visibility;
+    }
+
+    // The particles cast shadows too
+    if let Some(cloud) = scene.particles() {
+        let h = cloud.ray_intersect_at(origin, l_dir, ObjectId::PARTICLES, time);
+        if h.is_intersecting && h.distance < light_dist {
+            return 0.0;
+        }
+    }
+    1.0
+}
@@ -38,6 +38,26 @@ impl Default for Material {
     }
 }
 
+impl Material {
+    /// Stable id built from the fields that define the look (for the material id AOV).
+    pub fn id(&self) -> u32 {
+        let mut h: u32 = 0x811c_9dc5; // FNV-1a
+        let mut feed = |v: u32| {
+            for b in v.to_le_bytes() {
+                h ^= b as u32;
+                h = h.wrapping_mul(0x0100_0193);
+            }
+        };
+        feed(self.texture.map_or(0, |c| c as u32));
+        feed(u32::from_le_bytes([self.diffuse.r, self.diffuse.g, self.diffuse.b, self.diffuse.a]));
+        feed(self.albedo.to_bits());
+        feed(self.specular_strength.to_bits());
+        feed(self.reflectivity.to_bits());
+        feed(self.transparency.to_bits());
+        feed(self.emission_strength.to_bits());
+        h & 0x00ff_ffff // fits exactly in an f32 channel
+    }
+}
 
 pub fn dirt() -> Material {
         Material {
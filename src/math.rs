@@ -32,8 +32,36 @@ pub fn fresnel_schlick(cos_theta: f32, f0: f32) -> f32 {
     f0 + (1.0 - f0) * (1.0 - cos_theta).powf(5.0)
 }
 
-pub fn f0_from_ior(ior: f32) -> f32 {
-    // for dielectrics
-    let r0 = (ior - 1.0) / (ior + 1.0);
-    r0 * r0
-}
\ No newline at end of file
+// Ray vs AABB (slab test) up to `max_t`; `inv` is 1/direction per axis
+pub fn slab_hit(min: &Vector3, max: &Vector3, ro: &Vector3, inv: &Vector3, max_t: f32) -> bool {
+    let mut t0 = 0.0_f32;
+    let mut t1 = max_t;
+    for (o, i, lo, hi) in [(ro.x, inv.x, min.x, max.x), (ro.y, inv.y, min.y, max.y), (ro.z, inv.z, min.z, max.z)] {
+        let mut ta = (lo - o) * i;
+        let mut tb = (hi - o) * i;
+        if ta > tb {
+            std::mem::swap(&mut ta, &mut tb);
+        }
+        t0 = t0.max(ta);
+        t1 = t1.min(tb);
+        if t1 < t0 {
+            return false;
+        }
+    }
+    true
+}
+
+// Two unit tangents perpendicular to the unit vector `n`
+pub fn orthonormal_basis(n: Vector3) -> (Vector3, Vector3) {
+    let helper = if n.y.abs() < 0.99 { Vector3::new(0.0, 1.0, 0.0) } else { Vector3::new(1.0, 0.0, 0.0) };
+    let t = helper.cross(n).normalized();
+    (t, n.cross(t))
+}
+
+// Cosine-weighted direction around `n` from two uniforms in [0,1)
+pub fn cosine_hemisphere(n: Vector3, u1: f32, u2: f32) -> Vector3 {
+    let (t, b) = orthonormal_basis(n);
+    let r = u1.sqrt();
+    let phi = u2 * std::f32::consts::TAU;
+    (t * (r * phi.cos()) + b * (r * phi.sin()) + n * (1.0 - u1).max(0.0).sqrt()).normalized()
+}
@@ -0,0 +1,237 @@
+// media.rs
+use raylib::prelude::*;
+
+use crate::{
+    clouds::henyey_greenstein,
+    color::srgb_to_linear,
+    light::shadow_visibility,
+    scene::Scene,
+    skybox::Sky,
+};
+
+// -------- Niebla global (exponencial + por altura) --------
+#[derive(Clone, Copy, Debug)]
+pub struct Fog {
+    pub density: f32,         // global exponential fog, extinction per world unit
+    pub height_density: f32,  // height fog extinction at `base_height`
+    pub height_falloff: f32,  // how fast height fog thins out going up
+    pub base_height: f32,     // world y where height fog has `height_density`
+    pub max_distance: f32,    // distance used for rays that escape to the sky
+}
+
+impl Default for Fog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Fog {
+    pub fn new() -> Self {
+        Fog {
+            density: 0.004,
+            height_density: 0.06,
+            height_falloff: 0.6,
+            base_height: -2.0,
+            max_distance: 60.0,
+        }
+    }
+
+    /// Optical depth of the fog along `ro + rd * t` for t in [0, dist].
+    pub fn optical_depth(&self, ro: &Vector3, rd: &Vector3, dist: f32) -> f32 {
+        let global = self.density * dist;
+
+        // ∫ h * exp(-k * (y(t) - base)) dt, with y(t) = ro.y + rd.y * t
+        let k = self.height_falloff;
+        let start = self.height_density * (-k * (ro.y - self.base_height)).exp();
+        let slope = k * rd.y;
+        let height = if slope.abs() < 1e-5 {
+            start * dist
+        } else {
+            start * (1.0 - (-slope * dist).exp()) / slope
+        };
+
+        global + height.max(0.0)
+    }
+
+    /// Blends `color` (linear) with the sky-tinted fog color over `dist` world units.
+    pub fn apply(&self, color: Vector3, ro: &Vector3, rd: &Vector3, dist: f32, sky: &Sky) -> Vector3 {
+        let dist = dist.min(self.max_distance);
+        let transmittance = (-self.optical_depth(ro, rd, dist)).exp();
+        if transmittance >= 0.999 {
+            return color;
+        }
+        color * transmittance + sky.fog_color(*rd) * (1.0 - transmittance)
+    }
+}
+
+// -------- Volumen homogéneo (caja) --------
+#[derive(Clone, Copy, Debug)]
+pub struct Volume {
+    pub min: Vector3,
+    pub max: Vector3,
+    pub density: f32,             // extinction per world unit
+    pub albedo: Color,            // scattering color (sRGB)
+    pub anisotropy: f32,          // Henyey–Greenstein g, >0 = forward scattering
+    pub emission: Color,          // self glow color (sRGB)
+    pub emission_strength: f32,
+    pub steps: u32,               // raymarch steps across the box
+}
+
+impl Volume {
+    pub fn new(min: Vector3, max: Vector3, density: f32, albedo: Color) -> Self {
+        Volume {
+            min,
+            max,
+            density,
+            albedo,
+            anisotropy: 0.4,
+            emission: Color::BLACK,
+            emission_strength: 0.0,
+            steps: 16,
+        }
+    }
+
+    /// Entry/exit distances of the ray inside the box, clipped to [0, max_t].
+    pub fn span(&self, ro: &Vector3, rd: &Vector3, max_t: f32) -> Option<(f32, f32)> {
+        let mut t0 = 0.0_f32;
+        let mut t1 = max_t;
+        for (o, d, lo, hi) in [
+            (ro.x, rd.x, self.min.x, self.max.x),
+            (ro.y, rd.y, self.min.y, self.max.y),
+            (ro.z, rd.z, self.min.z, self.max.z),
+        ] {
+            if d.abs() < 1e-8 {
+                if o < lo || o > hi {
+                    return None;
+                }
+                continue;
+            }
+            let inv = 1.0 / d;
+            let mut ta = (lo - o) * inv;
+            let mut tb = (hi - o) * inv;
+            if ta > tb {
+                std::mem::swap(&mut ta, &mut tb);
+            }
+            t0 = t0.max(ta);
+            t1 = t1.min(tb);
+            if t1 <= t0 {
+                return None;
+            }
+        }
+        Some((t0, t1))
+    }
+
+    /// Beer–Lambert transmittance through the box along a segment of length `max_t`.
+    pub fn transmittance(&self, ro: &Vector3, rd: &Vector3, max_t: f32) -> f32 {
+        match self.span(ro, rd, max_t) {
+            Some((t0, t1)) => (-self.density * (t1 - t0)).exp(),
+            None => 1.0,
+        }
+    }
+
+    /// Raymarches the box with single scattering from the scene's lights.
+    /// Returns (in-scattered linear color, transmittance) for the part of the ray before `max_t`.
+    pub fn march(&self, ro: &Vector3, rd: &Vector3, max_t: f32, scene: &Scene, time: f32) -> (Vector3, f32) {
+        let sky = &scene.sky;
+        let Some((t0, t1)) = self.span(ro, rd, max_t) else {
+            return (Vector3::zero(), 1.0);
+        };
+
+        let (ar, ag, ab) = srgb_to_linear(self.albedo);
+        let albedo = Vector3::new(ar, ag, ab);
+        let (er, eg, eb) = srgb_to_linear(self.emission);
+        let emission = Vector3::new(er, eg, eb) * self.emission_strength;
+
+        let steps = self.steps.max(1);
+        let dt = (t1 - t0) / steps as f32;
+        let step_t = (-self.density * dt).exp();
+        let mut transmittance = 1.0;
+        let mut scattered = Vector3::zero();
+
+        for i in 0..steps {
+            let t = t0 + (i as f32 + 0.5) * dt;
+            let p = *ro + *rd * t;
+
+            // Constant ambient term so the haze is never pitch black
+            let mut radiance = emission + albedo * sky.ambient;
+
+            for light in scene.lights() {
+                if light.intensity <= 0.0 {
+                    continue;
+                }
+                let to_light = light.position_at(time) - p;
+                let light_dist = to_light.length();
+                let l_dir = to_light / light_dist;
+                let intensity = light.intensity * light.falloff(light_dist);
+                if intensity <= 0.0 {
+                    continue;
+                }
+
+                // Shadow rays through the scene are what turn into light shafts
+                let mut vis = shadow_visibility(&p, &l_dir, light_dist, light.emitter, scene, time);
+                if vis <= 0.0 {
+                    continue;
+                }
+                vis *= self.transmittance(&p, &l_dir, light_dist);
+                if light.emitter.is_none() {
+                    vis *= sky.cloud_shadow(p, l_dir);
+                }
+
+                let (lr, lg, lb) = srgb_to_linear(light.color);
+                let phase = henyey_greenstein(rd.dot(l_dir), self.anisotropy);
+                radiance += Vector3::new(lr * albedo.x, lg * albedo.y, lb * albedo.z)
+                    * (intensity * vis * phase);
+            }
+
+            scattered += radiance * (transmittance * (1.0 - step_t));
+            transmittance *= step_t;
+        }
+
+        (scattered, transmittance)
+    }
+}
+
+const MAX_STACK_VOLUMES: usize = 8; // more volumes than this are sorted in a heap buffer
+
+/// Applies the scene's volumes and fog to the linear `color` seen at distance `dist` along the ray.
+pub fn apply_media(color: Vector3, ro: &Vector3, rd: &Vector3, dist: f32, scene: &Scene, time: f32) -> Vector3 {
+    let mut color = color;
+    let volumes = &scene.volumes;
+
+    // Composite back to front so nearer volumes cover farther ones. This runs for every ray,
+    // so the usual handful of volumes is sorted on the stack
+    let mut composite = |hits: &mut [(f32, usize)]| {
+        hits.sort_unstable_by(|a, b| b.0.total_cmp(&a.0));
+        for &(_, i) in hits.iter() {
+            let (scattered, transmittance) = volumes[i].march(ro, rd, dist, scene, time);
+            color = color * transmittance + scattered;
+        }
+    };
+    let crossed = volumes.iter().enumerate().filter_map(|(i, v)| v.span(ro, rd, dist).map(|(t0, _)| (t0, i)));
+    if volumes.len() <= MAX_STACK_VOLUMES {
+        let mut hits = [(0.0, 0); MAX_STACK_VOLUMES];
+        let mut n = 0;
+        for hit in crossed {
+            hits[n] = hit;
+            n += 1;
+        }
+        composite(&mut hits[..n]);
+    } else {
+        composite(&mut crossed.collect::<Vec<_>>());
+    }
+
+    scene.sky.fog.apply(color, ro, rd, dist, &scene.sky)
+}
+
+pub fn sample_volumes() -> Vec<Volume> {
+    // Nether haze in front of the portal frame
+    let mut haze = Volume::new(
+        Vector3::new(1.5, 0.0, -1.5),
+        Vector3::new(3.5, 3.5, 1.5),
+        0.35,
+        Color::new(210, 120, 255, 255),
+    );
+    haze.emission = Color::new(150, 40, 220, 255);
+    haze.emission_strength = 0.15;
+    vec![haze]
+}
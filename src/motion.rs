@@ -0,0 +1,97 @@
+// motion.rs
+use raylib::prelude::*;
+
+pub const MAX_MOTION_KEYS: usize = 4;
+
+// Traslación en función del tiempo dentro del obturador (segundos desde que abre)
+#[derive(Clone, Copy, Debug)]
+pub enum Motion {
+    Static,
+    Linear { velocity: Vector3 }, // world units per second
+    Keyframed {
+        keys: [(f32, Vector3); MAX_MOTION_KEYS], // (time, offset), sorted by time
+        count: usize,
+    },
+}
+
+impl Motion {
+    /// Keyframed motion from up to `MAX_MOTION_KEYS` (time, offset) pairs; extra keys are dropped.
+    pub fn keyframed(keys: &[(f32, Vector3)]) -> Self {
+        let count = keys.len().min(MAX_MOTION_KEYS);
+        if count == 0 {
+            return Motion::Static;
+        }
+        let mut fixed = [(0.0, Vector3::zero()); MAX_MOTION_KEYS];
+        fixed[..count].copy_from_slice(&keys[..count]);
+        fixed[..count].sort_by(|a, b| a.0.total_cmp(&b.0));
+        Motion::Keyframed { keys: fixed, count }
+    }
+
+    pub fn is_static(&self) -> bool {
+        matches!(self, Motion::Static)
+    }
+
+    /// Offset from the rest position at `time` seconds after the shutter opens.
+    pub fn offset(&self, time: f32) -> Vector3 {
+        match *self {
+            Motion::Static => Vector3::zero(),
+            Motion::Linear { velocity } => velocity * time,
+            Motion::Keyframed { keys, count } => {
+                let keys = &keys[..count];
+                if time <= keys[0].0 {
+                    return keys[0].1;
+                }
+                for w in keys.windows(2) {
+                    let (t0, a) = w[0];
+                    let (t1, b) = w[1];
+                    if time <= t1 {
+                        let u = (time - t0) / (t1 - t0).max(1e-6);
+                        return a.lerp(b, u);
+                    }
+                }
+                keys[count - 1].1
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f32) -> Vector3 {
+        Vector3::new(x, 0.0, 0.0)
+    }
+
+    #[test]
+    fn static_and_linear_offsets() {
+        assert_eq!(Motion::Static.offset(0.7), Vector3::zero());
+        let linear = Motion::Linear { velocity: Vector3::new(2.0, 0.0, -4.0) };
+        assert_eq!(linear.offset(0.0), Vector3::zero());
+        assert_eq!(linear.offset(0.25), Vector3::new(0.5, 0.0, -1.0));
+    }
+
+    #[test]
+    fn keyframes_interpolate_and_clamp() {
+        // given out of order: `keyframed` sorts them by time
+        let m = Motion::keyframed(&[(0.5, v(4.0)), (0.0, v(0.0)), (0.25, v(1.0))]);
+        assert_eq!(m.offset(-1.0), v(0.0)); // before the first key
+        assert_eq!(m.offset(0.25), v(1.0)); // on a key
+        assert_eq!(m.offset(0.125), v(0.5));
+        assert_eq!(m.offset(0.375), v(2.5));
+        assert_eq!(m.offset(2.0), v(4.0)); // after the last key
+    }
+
+    #[test]
+    fn keyframed_edge_cases() {
+        assert!(Motion::keyframed(&[]).is_static());
+        let single = Motion::keyframed(&[(0.3, v(5.0))]);
+        assert_eq!(single.offset(0.0), v(5.0));
+        assert_eq!(single.offset(1.0), v(5.0));
+
+        // extra keys past MAX_MOTION_KEYS are dropped
+        let many: Vec<(f32, Vector3)> = (0..MAX_MOTION_KEYS + 3).map(|i| (i as f32, v(i as f32))).collect();
+        let m = Motion::keyframed(&many);
+        assert_eq!(m.offset(100.0), v((MAX_MOTION_KEYS - 1) as f32));
+    }
+}
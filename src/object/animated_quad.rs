@@ -1,7 +1,7 @@
 // animated_quad.rs
 use raylib::prelude::*;
 use crate::material::Material;
-use crate::ray_intersect::{Hit, RayIntersect};
+use crate::ray_intersect::{Hit, ObjectId, RayIntersect};
 
 #[derive(Clone, Copy, Debug)]
 pub struct AnimatedQuad {
@@ -42,7 +42,7 @@ impl AnimatedQuad {
 }
 
 impl RayIntersect for AnimatedQuad {
-    fn ray_intersect(&self, ro: &Vector3, rd: &Vector3, obj_id: usize) -> Hit {
+    fn ray_intersect(&self, ro: &Vector3, rd: &Vector3, obj_id: ObjectId) -> Hit {
         let n = self.normal;
         let denom = n.dot(*rd);
         // Ray nearly parallel to plane
@@ -0,0 +1,241 @@
+// billboard.rs
+use std::collections::HashMap;
+
+use raylib::prelude::*;
+
+use crate::bvh::Bvh;
+use crate::camera::Camera;
+use crate::light::PointLight;
+use crate::material::Material;
+use crate::ray_intersect::{Hit, ObjectId, RayIntersect};
+use crate::sprites::Sprite;
+use crate::textures::TextureManager;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BillboardFacing {
+    Camera, // quad always faces the camera
+    AxisY,  // quad stays vertical and only turns around world Y
+}
+
+// Partícula como quad intersectable (aparece en reflejos, refracciones y sombras)
+#[derive(Clone, Copy, Debug)]
+pub struct Billboard {
+    pub center: Vector3,
+    pub velocity: Vector3, // motion while the shutter is open
+    pub size: f32,         // side length in world units
+    pub tex_id: char,
+    pub tint: Color,
+    pub emission_strength: f32,
+}
+
+// Alpha of a texture, copied out of the TextureManager so intersection doesn't need it
+#[derive(Clone, Debug)]
+struct AlphaMask {
+    width: u32,
+    height: u32,
+    alpha: Vec<u8>,
+}
+
+impl AlphaMask {
+    fn sample(&self, u: f32, v: f32) -> u8 {
+        let x = ((u * self.width as f32) as u32).min(self.width - 1);
+        let y = (((1.0 - v) * self.height as f32) as u32).min(self.height - 1);
+        self.alpha[(y * self.width + x) as usize]
+    }
+}
+
+/// All particles of a frame, with a BVH over them.
+#[derive(Clone, Debug)]
+pub struct BillboardCloud {
+    pub billboards: Vec<Billboard>,
+    pub facing: BillboardFacing,
+    pub alpha_cutoff: u8,
+    right: Vector3, // quad axes for `BillboardFacing::Camera`
+    up: Vector3,
+    eye: Vector3,
+    masks: HashMap<char, AlphaMask>,
+    bvh: Bvh,
+}
+
+impl BillboardCloud {
+    /// Turns the live sprites into billboards facing `camera`, bounding their motion over `shutter`.
+    pub fn build(sprites: &[Sprite], camera: &Camera, texmgr: &TextureManager, facing: BillboardFacing, shutter: f32) -> Self {
+        let billboards: Vec<Billboard> = sprites
+            .iter()
+            .filter(|s| s.visible)
+            .map(|s| Billboard {
+                center: s.position,
+                velocity: s.velocity,
+                size: s.size_world,
+                tex_id: s.tex_id,
+                tint: s.tint,
+                emission_strength: s.emission,
+            })
+            .collect();
+
+        let mut masks = HashMap::new();
+        for b in &billboards {
+            masks.entry(b.tex_id).or_insert_with(|| alpha_mask(texmgr, b.tex_id));
+        }
+
+        let mut cloud = BillboardCloud {
+            billboards,
+            facing,
+            alpha_cutoff: 128,
+            right: camera.right,
+            up: camera.up,
+            eye: camera.eye,
+            masks,
+            bvh: Bvh::default(),
+        };
+        cloud.bvh = Bvh::build(cloud.billboards.iter().map(|b| Self::billboard_bounds(b, shutter)).enumerate().collect());
+        cloud
+    }
+
+    fn billboard_bounds(b: &Billboard, shutter: f32) -> (Vector3, Vector3) {
+        // a sphere around the quad covers every orientation
+        let r = Vector3::new(1.0, 1.0, 1.0) * (b.size * std::f32::consts::FRAC_1_SQRT_2);
+        let end = b.center + b.velocity * shutter;
+        (b.center.min(end) - r, b.center.max(end) + r)
+    }
+
+    /// Box around every particle over the shutter, `None` when there are none.
+    pub fn bounds(&self) -> Option<(Vector3, Vector3)> {
+        self.bvh.bounds()
+    }
+
+    // Quad axes (right, up) of a billboard
+    fn axes(&self, center: Vector3) -> (Vector3, Vector3) {
+        match self.facing {
+            BillboardFacing::Camera => (self.right, self.up),
+            BillboardFacing::AxisY => {
+                let world_up = Vector3::new(0.0, 1.0, 0.0);
+                let to_eye = self.eye - center;
+                let flat = Vector3::new(to_eye.x, 0.0, to_eye.z);
+                if flat.length() < 1e-5 {
+                    return (self.right, world_up);
+                }
+                (world_up.cross(flat.normalized()).normalized(), world_up)
+            }
+        }
+    }
+
+    fn intersect_billboard(&self, b: &Billboard, ro: &Vector3, rd: &Vector3, time: f32, max_t: f32) -> Option<(f32, Vector3, Vector2, Vector3)> {
+        let center = b.center + b.velocity * time;
+        let (right, up) = self.axes(center);
+        let normal = right.cross(up).normalized();
+
+        let denom = normal.dot(*rd);
+        if denom.abs() < 1e-6 {
+            return None;
+        }
+        let t = (center - *ro).dot(normal) / denom;
+        if t <= 1e-4 || t >= max_t {
+            return None;
+        }
+        let p = *ro + *rd * t;
+        let local = p - center;
+        let u = local.dot(right) / b.size + 0.5;
+        let v = local.dot(up) / b.size + 0.5;
+        if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+            return None;
+        }
+
+        // Alpha test against the sprite texture
+        if let Some(mask) = self.masks.get(&b.tex_id)
+            && mask.sample(u, v) < self.alpha_cutoff
+        {
+            return None;
+        }
+
+        // face the incoming ray
+        let n = if denom < 0.0 { normal } else { -normal };
+        Some((t, p, Vector2::new(u, v), n))
+    }
+
+    pub fn ray_intersect_at(&self, ro: &Vector3, rd: &Vector3, obj_id: ObjectId, time: f32) -> Hit {
+        let mut hit = Hit::no_hit();
+        self.bvh.visit(ro, rd, f32::INFINITY, |i, max_t| {
+            let b = &self.billboards[i];
+            if let Some((t, p, uv, n)) = self.intersect_billboard(b, ro, rd, time, *max_t) {
+                *max_t = t;
+                hit = Hit {
+                    is_intersecting: true,
+                    distance: t,
+                    point: p,
+                    normal: n,
+                    material: billboard_material(b),
+                    uv,
+                    obj_id,
+                    tex_id: Some(b.tex_id),
+                };
+            }
+            true
+        });
+        hit
+    }
+
+    /// Up to `max_lights` small point lights spread over the particles, so they light nearby blocks.
+    /// Only glowing particles (emission > 0) give light. Each light covers `radius` world units;
+    /// intensities are scaled so the total stays the same.
+    pub fn lights(&self, obj_id: ObjectId, max_lights: usize, intensity: f32, radius: f32) -> Vec<PointLight> {
+        if self.billboards.is_empty() || max_lights == 0 {
+            return Vec::new();
+        }
+        let glowing: Vec<&Billboard> = self.billboards.iter().filter(|b| b.emission_strength > 0.0).collect();
+        if glowing.is_empty() {
+            return Vec::new();
+        }
+        let stride = glowing.len().div_ceil(max_lights);
+        let share = stride as f32;
+        glowing
+            .into_iter()
+            .step_by(stride)
+            .map(|b| {
+                let mut light = PointLight::new(b.center, intensity * b.emission_strength * share, b.tint, Some(obj_id));
+                light.radius = radius;
+                light
+            })
+            .collect()
+    }
+}
+
+impl RayIntersect for BillboardCloud {
+    fn ray_intersect(&self, ro: &Vector3, rd: &Vector3, obj_id: ObjectId) -> Hit {
+        self.ray_intersect_at(ro, rd, obj_id, 0.0)
+    }
+}
+
+// Glowing particles are unlit and only show their own (tinted) glow, added in `cast_ray`;
+// the rest (smoke, leaves, rain) are shaded like any surface
+fn billboard_material(b: &Billboard) -> Material {
+    if b.emission_strength <= 0.0 {
+        return Material {
+            diffuse: b.tint,
+            albedo: 1.0,
+            texture: Some(b.tex_id),
+            ..Material::default()
+        };
+    }
+    Material {
+        diffuse: Color::WHITE,
+        albedo: 0.0,
+        emission: b.tint,
+        emission_strength: b.emission_strength,
+        texture: Some(b.tex_id),
+        ..Material::default()
+    }
+}
+
+fn alpha_mask(texmgr: &TextureManager, tex_id: char) -> AlphaMask {
+    let Some((width, height)) = texmgr.size(tex_id) else {
+        return AlphaMask { width: 1, height: 1, alpha: vec![255] };
+    };
+    let mut alpha = Vec::with_capacity((width * height) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            alpha.push(texmgr.get_pixel_color(tex_id, x, y).a);
+        }
+    }
+    AlphaMask { width, height, alpha }
+}
@@ -1,7 +1,7 @@
 // cube.rs
 use raylib::prelude::*;
 use crate::material::{Material, *};
-use crate::ray_intersect::{Hit, RayIntersect};
+use crate::ray_intersect::{Hit, ObjectId, RayIntersect};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Cube {
@@ -12,7 +12,7 @@ pub struct Cube {
 }
 
 impl RayIntersect for Cube {
-    fn ray_intersect(&self, ro: &Vector3, rd: &Vector3, obj_id: usize) -> Hit {
+    fn ray_intersect(&self, ro: &Vector3, rd: &Vector3, obj_id: ObjectId) -> Hit {
         // Safe inverses
         let invx = if rd.x.abs() < 1e-8 {
             1.0 / (if rd.x.is_sign_negative() { -1.0e-8 } else { 1.0e-8 })
@@ -1,6 +1,6 @@
 use raylib::prelude::*;
 use crate::material::Material;
-use crate::ray_intersect::{Hit, RayIntersect};
+use crate::ray_intersect::{Hit, ObjectId, RayIntersect};
 use crate::object::obj::Obj;
 
 #[derive(Clone, Copy, Debug)]
@@ -38,7 +38,7 @@ impl Mesh {
 }
 
 impl RayIntersect for Mesh {
-    fn ray_intersect(&self, ro: &Vector3, rd: &Vector3, obj_id: usize) -> Hit {
+    fn ray_intersect(&self, ro: &Vector3, rd: &Vector3, obj_id: ObjectId) -> Hit {
         let mut closest = Hit::no_hit();
 
         for tri in &self.triangles {
@@ -55,7 +55,7 @@ impl RayIntersect for Mesh {
             let inv_det = 1.0 / det;
             let tvec = *ro - tri.v0;
             let u = tvec.dot(pvec) * inv_det;
-            if u < 0.0 || u > 1.0 {
+            if !(0.0..=1.0).contains(&u) {
                 continue;
             }
 
@@ -81,7 +81,6 @@ impl RayIntersect for Mesh {
                 }
 
                 // For now, no UVs → fake them from barycentrics (optional)
-                let w = 1.0 - u - v;
                 let uv = Vector2::new(u, v); // or (barycentric-based, but we don't use textures here)
 
                 closest = Hit {
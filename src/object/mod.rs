@@ -4,10 +4,16 @@ mod animated_quad;
 mod cube;
 mod mesh;
 mod obj;
+mod moving;
+mod billboard;
 
-use crate::object::{animated_quad::AnimatedQuad, cube::Cube, mesh::Mesh, obj::Obj, sphere::Sphere};
-use crate:: ray_intersect::{Hit, RayIntersect};
+use crate::object::{animated_quad::AnimatedQuad, mesh::Mesh, obj::Obj, sphere::Sphere};
+pub use crate::object::cube::Cube;
+pub use crate::object::moving::MovingObject;
+pub use crate::object::billboard::{BillboardCloud, BillboardFacing};
+use crate::ray_intersect::{Hit, ObjectId, RayIntersect};
 use crate::material::{*, Material};
+use crate::motion::Motion;
 
 // -------- Objetos soportados --------
 #[derive(Clone, Debug)]
@@ -16,15 +22,112 @@ pub enum Object {
     Cube(Cube),
     AnimatedQuad(AnimatedQuad),
     Mesh(Mesh),
+    Moving(MovingObject),
+    Billboards(BillboardCloud), // rebuilt every frame from the sprite system
+}
+
+impl Object {
+    /// Like `ray_intersect`, but with moving objects placed where they are at `time`
+    /// (seconds since the shutter opened).
+    pub fn ray_intersect_at(&self, ro: &Vector3, rd: &Vector3, obj_id: ObjectId, time: f32) -> Hit {
+        match self {
+            Object::Moving(m) => m.ray_intersect_at(ro, rd, obj_id, time),
+            Object::Billboards(b) => b.ray_intersect_at(ro, rd, obj_id, time),
+            _ => self.ray_intersect(ro, rd, obj_id),
+        }
+    }
+
+    /// World-space bounding box, or `None` when it can't be bounded (linear motion keeps going).
+    pub fn bounds(&self) -> Option<(Vector3, Vector3)> {
+        match self {
+            Object::Sphere(s) => {
+                let r = Vector3::one() * s.radius;
+                Some((s.center - r, s.center + r))
+            }
+            Object::Cube(c) => Some((c.min, c.max)),
+            Object::AnimatedQuad(aq) => {
+                let right = aq.normal.cross(aq.up).normalized();
+                let ext = Vector3::new(
+                    (right.x * aq.width).abs() + (aq.up.x * aq.height).abs(),
+                    (right.y * aq.width).abs() + (aq.up.y * aq.height).abs(),
+                    (right.z * aq.width).abs() + (aq.up.z * aq.height).abs(),
+                ) * 0.5;
+                Some((aq.center - ext, aq.center + ext))
+            }
+            Object::Mesh(m) => {
+                let first = m.triangles.first()?.v0;
+                let (mut min, mut max) = (first, first);
+                for t in &m.triangles {
+                    for v in [t.v0, t.v1, t.v2] {
+                        min = min.min(v);
+                        max = max.max(v);
+                    }
+                }
+                Some((min, max))
+            }
+            Object::Moving(mv) => {
+                let (min, max) = mv.object.bounds()?;
+                match mv.motion {
+                    Motion::Static => Some((min, max)),
+                    Motion::Linear { .. } => None,
+                    // piecewise linear: the hull of the keyed positions covers the whole path
+                    Motion::Keyframed { keys, count } => {
+                        let (mut lo, mut hi) = (min, max);
+                        for (_, offset) in &keys[..count] {
+                            lo = lo.min(min + *offset);
+                            hi = hi.max(max + *offset);
+                        }
+                        Some((lo, hi))
+                    }
+                }
+            }
+            Object::Billboards(b) => b.bounds(),
+        }
+    }
+
+    /// Triangles the object would have as a mesh (spheres are analytic, so 0).
+    pub fn triangle_count(&self) -> usize {
+        match self {
+            Object::Sphere(_) => 0, // analytic
+            Object::Cube(_) => 12,
+            Object::AnimatedQuad(_) => 2,
+            Object::Mesh(m) => m.triangles.len(),
+            Object::Moving(m) => m.object.triangle_count(),
+            Object::Billboards(b) => 2 * b.billboards.len(),
+        }
+    }
+
+    /// Short name of the variant, for the HUD.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Object::Sphere(_) => "sphere",
+            Object::Cube(_) => "cube",
+            Object::AnimatedQuad(_) => "quad",
+            Object::Mesh(_) => "mesh",
+            Object::Moving(m) => m.object.kind(),
+            Object::Billboards(_) => "billboards",
+        }
+    }
+
+    /// Triangles of the object's surface, for `EmitterShape::mesh_surface` (meshes only for now).
+    pub fn surface_triangles(&self) -> Vec<[Vector3; 3]> {
+        match self {
+            Object::Mesh(m) => m.triangles.iter().map(|t| [t.v0, t.v1, t.v2]).collect(),
+            Object::Moving(m) => m.object.surface_triangles(),
+            _ => Vec::new(),
+        }
+    }
 }
 
 impl RayIntersect for Object {
-    fn ray_intersect(&self, ro: &Vector3, rd: &Vector3, obj_id: usize) -> Hit {
+    fn ray_intersect(&self, ro: &Vector3, rd: &Vector3, obj_id: ObjectId) -> Hit {
         match self {
             Object::Sphere(s) => s.ray_intersect(ro, rd, obj_id),
             Object::Cube(c)   => c.ray_intersect(ro, rd, obj_id),
             Object::AnimatedQuad(aq) => aq.ray_intersect(ro, rd, obj_id),
             Object::Mesh(m)         => m.ray_intersect(ro, rd, obj_id),
+            Object::Moving(m)       => m.ray_intersect(ro, rd, obj_id),
+            Object::Billboards(b)   => b.ray_intersect(ro, rd, obj_id),
         }
     }
 }
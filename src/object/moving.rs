@@ -0,0 +1,36 @@
+// moving.rs
+use raylib::prelude::*;
+use crate::motion::Motion;
+use crate::object::Object;
+use crate::ray_intersect::{Hit, ObjectId, RayIntersect};
+
+// Objeto que se traslada mientras el obturador está abierto (motion blur)
+#[derive(Clone, Debug)]
+pub struct MovingObject {
+    pub object: Box<Object>,
+    pub motion: Motion,
+}
+
+impl MovingObject {
+    pub fn new(object: Object, motion: Motion) -> Self {
+        MovingObject { object: Box::new(object), motion }
+    }
+
+    /// Intersects the object displaced to where it is at `time`.
+    pub fn ray_intersect_at(&self, ro: &Vector3, rd: &Vector3, obj_id: ObjectId, time: f32) -> Hit {
+        // Moving the object by `offset` is the same as moving the ray by `-offset`
+        let offset = self.motion.offset(time);
+        let local_origin = *ro - offset;
+        let mut hit = self.object.ray_intersect_at(&local_origin, rd, obj_id, time);
+        if hit.is_intersecting {
+            hit.point += offset;
+        }
+        hit
+    }
+}
+
+impl RayIntersect for MovingObject {
+    fn ray_intersect(&self, ro: &Vector3, rd: &Vector3, obj_id: ObjectId) -> Hit {
+        self.ray_intersect_at(ro, rd, obj_id, 0.0)
+    }
+}
@@ -1,5 +1,4 @@
-use raylib::math::{Vector2, Vector3};
-use tobj;
+use raylib::math::Vector3;
 
 pub struct Obj {
     pub vertices: Vec<Vector3>,
@@ -33,7 +32,7 @@ impl Obj {
     pub fn get_vertex_array(&self) -> Vec<Vector3> {
         let mut vertex_array = Vec::new();
         for &index in &self.indices {
-            vertex_array.push(self.vertices[index as usize].clone());
+            vertex_array.push(self.vertices[index as usize]);
         }
         vertex_array
     }
@@ -1,6 +1,6 @@
 use raylib::prelude::*;
 use crate::material::Material;
-use crate::ray_intersect::{Hit, RayIntersect};
+use crate::ray_intersect::{Hit, ObjectId, RayIntersect};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Sphere {
@@ -10,7 +10,7 @@ pub struct Sphere {
 }
 
 impl RayIntersect for Sphere {
-    fn ray_intersect(&self, ro: &Vector3, rd: &Vector3, obj_id: usize) -> Hit {
+    fn ray_intersect(&self, ro: &Vector3, rd: &Vector3, obj_id: ObjectId) -> Hit {
         let oc = *ro - self.center;
         let a = rd.dot(*rd);
         let b = 2.0 * oc.dot(*rd);
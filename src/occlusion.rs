@@ -0,0 +1,202 @@
+// occlusion.rs
+use std::collections::HashSet;
+
+use rand::Rng;
+use raylib::prelude::*;
+
+use crate::{
+    math::cosine_hemisphere,
+    object::Object,
+    ray_intersect::{Hit, ObjectId},
+    scene::Scene,
+};
+
+// -------- Oclusión ambiental --------
+
+/// How the sky ambient term is occluded in `cast_ray`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AoMode {
+    Off, // constant ambient, as before
+    /// Cosine-weighted rays over the hemisphere, blocked by anything closer than `radius`.
+    Hemisphere { radius: f32, samples: u32 },
+    /// Minecraft-style smooth lighting: each block face corner looks at its three neighbours
+    /// in the voxel grid, and the four corner values are blended over the face. Only faces
+    /// of grid blocks get darkened; other surfaces keep the full ambient.
+    VoxelCorners,
+}
+
+impl AoMode {
+    pub fn hemisphere() -> Self {
+        AoMode::Hemisphere { radius: 1.0, samples: 8 }
+    }
+
+    /// Off → voxel corners → hemisphere → off.
+    pub fn next(self) -> Self {
+        match self {
+            AoMode::Off => AoMode::VoxelCorners,
+            AoMode::VoxelCorners => AoMode::hemisphere(),
+            AoMode::Hemisphere { .. } => AoMode::Off,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "off" => Some(AoMode::Off),
+            "voxel" => Some(AoMode::VoxelCorners),
+            "hemisphere" => Some(AoMode::hemisphere()),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AoMode::Off => "off",
+            AoMode::VoxelCorners => "voxel",
+            AoMode::Hemisphere { .. } => "hemisphere",
+        }
+    }
+
+    /// Factor for the ambient term at a hit: 1.0 = open sky, lower in creases and corners.
+    pub fn occlusion(self, hit: &Hit, scene: &Scene, time: f32) -> f32 {
+        match self {
+            AoMode::Off => 1.0,
+            AoMode::Hemisphere { radius, samples } => {
+                // particles don't occlude; treat their quads as open
+                if hit.obj_id == ObjectId::PARTICLES {
+                    return 1.0;
+                }
+                ambient_occlusion(scene, hit.point, hit.normal, radius, samples, time, &mut rand::thread_rng())
+            }
+            AoMode::VoxelCorners => scene.voxels().corner_occlusion(hit.point, hit.normal).unwrap_or(1.0),
+        }
+    }
+}
+
+/// Fraction of the hemisphere around `normal` that is open within `radius` (1.0 = unoccluded),
+/// from `samples` cosine-weighted rays. Each ray is occluded by its nearest blocker, less the
+/// farther away it is; transparent blockers only occlude partly.
+pub fn ambient_occlusion(
+    scene: &Scene,
+    point: Vector3,
+    normal: Vector3,
+    radius: f32,
+    samples: u32,
+    time: f32,
+    rng: &mut impl Rng,
+) -> f32 {
+    let n = samples.max(1);
+    let origin = point + normal * 1e-3;
+    let mut open = 0.0;
+    for i in 0..n {
+        // stratified in the first dimension
+        let u1 = (i as f32 + rng.r#gen::<f32>()) / n as f32;
+        let dir = cosine_hemisphere(normal, u1, rng.r#gen());
+        // the BVH hands objects over in node order, not by distance: keep the nearest blocker
+        let mut nearest: Option<(f32, f32)> = None; // (distance, transparency)
+        scene.visit(&origin, &dir, radius, |id, obj| {
+            let h = obj.ray_intersect_at(&origin, &dir, id, time);
+            if h.is_intersecting && h.distance < nearest.map_or(radius, |(d, _)| d) {
+                nearest = Some((h.distance, h.material.transparency.clamp(0.0, 1.0)));
+            }
+            true
+        });
+        open += match nearest {
+            // blockers fade out towards `radius`, so the occlusion has no hard edge
+            Some((d, transparency)) => {
+                let falloff = (d / radius).clamp(0.0, 1.0);
+                transparency + (1.0 - transparency) * falloff * falloff
+            }
+            None => 1.0,
+        };
+    }
+    open / n as f32
+}
+
+// -------- Rejilla de bloques --------
+
+// Brightness of a face corner by how many of its 3 neighbours are solid (3 → 0)
+const CORNER_LEVELS: [f32; 4] = [0.35, 0.55, 0.78, 1.0];
+
+/// Which unit cells of the world hold an opaque block (a unit `Cube` on integer coordinates).
+#[derive(Clone, Debug, Default)]
+pub struct VoxelGrid {
+    cells: HashSet<(i32, i32, i32)>,
+}
+
+impl VoxelGrid {
+    pub fn from_objects<'a>(objects: impl IntoIterator<Item = (ObjectId, &'a Object)>) -> Self {
+        let mut cells = HashSet::new();
+        for (_, obj) in objects {
+            if let Object::Cube(c) = obj
+                && c.material.transparency <= 0.0
+                && let Some(cell) = unit_cell(c.min, c.max)
+            {
+                cells.insert(cell);
+            }
+        }
+        VoxelGrid { cells }
+    }
+
+    pub fn is_solid(&self, cell: (i32, i32, i32)) -> bool {
+        self.cells.contains(&cell)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Smooth-lighting factor at a point on a block face, or None if the point isn't on one.
+    pub fn corner_occlusion(&self, point: Vector3, normal: Vector3) -> Option<f32> {
+        let n = [normal.x, normal.y, normal.z];
+        let axis = (0..3).max_by(|&a, &b| n[a].abs().total_cmp(&n[b].abs()))?;
+        if n[axis].abs() < 0.99 {
+            return None; // not an axis-aligned face
+        }
+        let step = if n[axis] > 0.0 { 1 } else { -1 };
+
+        // the block that was hit, and the cell in front of the face
+        let inside = point - normal * 0.5;
+        let block = [inside.x.floor() as i32, inside.y.floor() as i32, inside.z.floor() as i32];
+        if !self.is_solid((block[0], block[1], block[2])) {
+            return None;
+        }
+        let mut front = block;
+        front[axis] += step;
+
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+        let p = [point.x, point.y, point.z];
+        let fu = (p[u] - block[u] as f32).clamp(0.0, 1.0);
+        let fv = (p[v] - block[v] as f32).clamp(0.0, 1.0);
+
+        let solid = |du: i32, dv: i32| {
+            let mut c = front;
+            c[u] += du;
+            c[v] += dv;
+            self.is_solid((c[0], c[1], c[2])) as usize
+        };
+        let corner = |du: i32, dv: i32| {
+            let (s1, s2, c) = (solid(du, 0), solid(0, dv), solid(du, dv));
+            // two solid sides hide the corner block completely
+            let blocked = if s1 == 1 && s2 == 1 { 3 } else { s1 + s2 + c };
+            CORNER_LEVELS[3 - blocked]
+        };
+
+        // bilinear blend of the four corners over the face
+        let bottom = corner(-1, -1) * (1.0 - fu) + corner(1, -1) * fu;
+        let top = corner(-1, 1) * (1.0 - fu) + corner(1, 1) * fu;
+        Some(bottom * (1.0 - fv) + top * fv)
+    }
+}
+
+/// Integer cell of a unit box on the grid (a block), if it is one.
+pub fn unit_cell(min: Vector3, max: Vector3) -> Option<(i32, i32, i32)> {
+    let size = max - min;
+    let on_grid = |x: f32| (x - x.round()).abs() < 1e-4;
+    let unit = |x: f32| (x - 1.0).abs() < 1e-4;
+    (unit(size.x) && unit(size.y) && unit(size.z) && on_grid(min.x) && on_grid(min.y) && on_grid(min.z))
+        .then(|| (min.x.round() as i32, min.y.round() as i32, min.z.round() as i32))
+}
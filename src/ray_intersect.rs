@@ -3,6 +3,15 @@ use raylib::prelude::*;
 
 use crate::material::Material;
 
+/// Stable handle of an object in a `Scene`; survives other objects being added or removed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ObjectId(pub u32);
+
+impl ObjectId {
+    /// The frame's particle billboards, which live outside the object list.
+    pub const PARTICLES: ObjectId = ObjectId(u32::MAX);
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Hit {
     pub is_intersecting: bool,
@@ -11,7 +20,7 @@ pub struct Hit {
     pub normal: Vector3,
     pub material: Material,
     pub uv: Vector2,
-    pub obj_id: usize,
+    pub obj_id: ObjectId,
     pub tex_id: Option<char>
 }
 
@@ -24,12 +33,12 @@ impl Hit {
             normal: Vector3::zero(),
             material: Material::default(),
             uv: Vector2::zero(),
-            obj_id: 0,
+            obj_id: ObjectId(0),
             tex_id: None,
         }
     }
 }
 
 pub trait RayIntersect {
-    fn ray_intersect(&self, ray_origin: &Vector3, ray_dir: &Vector3, obj_id: usize) -> Hit;
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_dir: &Vector3, obj_id: ObjectId) -> Hit;
 }
\ No newline at end of file
@@ -0,0 +1,673 @@
+// renderer.rs
+use std::{
+    cell::RefCell,
+    sync::{
+        Mutex, mpsc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use raylib::prelude::*;
+use rayon::prelude::*;
+
+use crate::{
+    animation::{CameraPath, FrameClock},
+    aov::{Aov, AovBuffer, AovSample},
+    camera::Camera,
+    color::*,
+    denoise::{DenoiseMode, Denoiser},
+    framebuffer::Framebuffer,
+    image_io::ExrPixelType,
+    integrator::{Integrator, Whitted},
+    light::shadow_visibility,
+    math::*,
+    media::apply_media,
+    motion::{MAX_MOTION_KEYS, Motion},
+    object::Object,
+    ray_intersect::{Hit, ObjectId},
+    scene::{Scene, rays_traced},
+    skybox::Sky,
+    temporal::TemporalCache,
+    textures::TextureManager,
+    tiles::{Progress, Tile, TileOrder, tiles},
+};
+
+const MAX_DEPTH: u32 = 4;
+// -------- trazado con Lambert + sombra --------
+pub fn cast_ray(
+    ro: &Vector3,
+    rd: &Vector3,
+    scene: &Scene,
+    depth: u32,
+    time: f32,
+    aov: Option<&mut AovSample>,
+) -> Vector3 {
+    let sky = &scene.sky;
+    if depth >= MAX_DEPTH {
+        //return Color::new(4, 12, 36, 255); // background
+        let default = sky.procedural_sky(*rd);
+        return apply_media(default, ro, rd, f32::INFINITY, scene, time);
+    }
+    // Buscar el hit más cercano
+    let closest = scene.intersect(ro, rd, time);
+    if !closest.is_intersecting {
+        //return Color::new(4, 12, 36, 255);
+        let default = sky.procedural_sky(*rd);
+        return apply_media(default, ro, rd, f32::INFINITY, scene, time);
+    }
+
+    let m = closest.material;
+    let base_srgb = surface_color(&closest, &scene.textures, sky);
+
+    // Convert to linear for lighting
+    let (br, bg, bb) = srgb_to_linear(base_srgb);
+    let base = Vector3::new(br, bg, bb);
+
+    //let ambient = 0.05;
+
+    let (light, shadow_total, shadow_blocked) = direct_lighting(&closest, ro, base, scene, time);
+    // Sky ambient, darkened in creases and corners
+    // (with voxel lighting, plus the block light of nearby lamps)
+    let occlusion = scene.ao.occlusion(&closest, scene, time);
+    let ambient_light = scene.ambient_light(&closest) * (m.albedo * occlusion);
+    let local = Vector3::new(br * ambient_light.x, bg * ambient_light.y, bb * ambient_light.z)
+        + light
+        + particle_glow(&closest, base, scene);
+    let (lr, lg, lb) = (local.x, local.y, local.z);
+
+    let eps = 1e-3;
+    // --- Fresnel-based mixing ---
+    // cos_theta: angle between view direction and surface normal
+    // rd points TOWARDS the scene, so view dir is -rd.
+    let cos_theta = (-rd.dot(closest.normal)).max(0.0);
+
+    // Base reflectivity at normal incidence (f0)
+    // Option A: use your material.reflectivity as f0 (0..1)
+    let f0 = m.reflectivity.clamp(0.0, 1.0);
+
+    // Option B (more physical for dielectrics):
+    // let f0 = f0_from_ior(m.ior);
+
+    // Fresnel reflection factor depending on angle
+    let fresnel = fresnel_schlick(cos_theta, f0);
+
+    // kr: reflection factor (angle-dependent)
+    // kt: transparency factor (constant for now)
+    let kr = fresnel;
+    let kt = m.transparency.clamp(0.0, 1.0);
+
+    // kd: leftover part for local (diffuse+specular) lighting
+    let mut kd = 1.0 - kr - kt;
+    if kd < 0.0 { kd = 0.0; }
+
+    // Start final color with local lighting scaled by kd
+    let mut fr = lr * kd;
+    let mut fg = lg * kd;
+    let mut fb = lb * kd;
+    let direct = Vector3::new(fr, fg, fb);
+
+    // --- Reflection contribution ---
+    if kr > 0.0 {
+        let refl_dir = reflect(*rd, closest.normal).normalized();
+        let refl_origin = closest.point + closest.normal * eps;
+        let refl = cast_ray(&refl_origin, &refl_dir, scene, depth + 1, time, None);
+
+        fr += refl.x * kr;
+        fg += refl.y * kr;
+        fb += refl.z * kr;
+    }
+
+    // --- Refraction contribution ---
+    if kt > 0.0
+        && let Some(refr_dir) = refract(*rd, closest.normal, 1.0, m.ior)
+    {
+        let refr_origin = closest.point - closest.normal * eps; // slightly inside
+        let refr = cast_ray(&refr_origin, &refr_dir.normalized(), scene, depth + 1, time, None);
+
+        fr += refr.x * kt;
+        fg += refr.y * kt;
+        fb += refr.z * kt;
+    }
+
+    if let Some(aov) = aov {
+        let (ar, ag, ab) = srgb_to_linear(base_srgb);
+        let (er, eg, eb) = srgb_to_linear(m.emission);
+        *aov = AovSample {
+            depth: closest.distance,
+            position: closest.point,
+            normal: closest.normal,
+            albedo: Vector3::new(ar, ag, ab),
+            obj_id: Some(closest.obj_id),
+            material_id: m.id(),
+            uv: closest.uv,
+            direct,
+            indirect: Vector3::new(fr, fg, fb) - direct,
+            emission: Vector3::new(er, eg, eb) * m.emission_strength,
+            shadow: if shadow_total > 0.0 { shadow_blocked / shadow_total } else { 0.0 },
+        };
+    }
+
+    // Fog and volumes between the eye and the surface (linear, unclamped)
+    apply_media(Vector3::new(fr, fg, fb), ro, rd, closest.distance, scene, time)
+}
+
+/// Diffuse + specular light reaching a hit from every light in the scene (no ambient term),
+/// plus the light that could reach it and the part of it that got blocked (for the shadow mask).
+pub fn direct_lighting(closest: &Hit, ro: &Vector3, base: Vector3, scene: &Scene, time: f32) -> (Vector3, f32, f32) {
+    let m = closest.material;
+    let (br, bg, bb) = (base.x, base.y, base.z);
+    let (mut lr, mut lg, mut lb) = (0.0, 0.0, 0.0);
+    let mut shadow_total = 0.0;
+    let mut shadow_blocked = 0.0;
+
+    // view direction (towards camera)
+    let view_dir = (*ro - closest.point).normalized();
+
+    for light in scene.lights() {
+        let (lr_l, lg_l, lb_l) = srgb_to_linear(light.color);
+        let to_light = light.position_at(time) - closest.point;
+        let light_dist = to_light.length();
+        let l_dir = to_light / light_dist;
+        // Lights with a radius (particles) only reach nearby surfaces
+        let intensity = light.intensity * light.falloff(light_dist);
+        if intensity <= 0.0 {
+            continue;
+        }
+
+        // Shadow ray with transparency-aware visibility
+        let ndotl_raw = closest.normal.dot(l_dir).clamp(-1.0, 1.0);
+        let bias = 5e-3 + 5e-3 * (1.0 - ndotl_raw.abs());
+        let shadow_origin = closest.point + closest.normal * if ndotl_raw >= 0.0 { bias } else { -bias };
+
+        let mut light_visibility = shadow_visibility(&shadow_origin, &l_dir, light_dist, light.emitter, scene, time);
+
+        // Participating media between the surface and the light
+        for v in &scene.volumes {
+            light_visibility *= v.transmittance(&shadow_origin, &l_dir, light_dist);
+        }
+
+        // Sun and moon (no emitter) can be hidden behind clouds
+        if light_visibility > 0.0 && light.emitter.is_none() {
+            light_visibility *= scene.sky.cloud_shadow(shadow_origin, l_dir);
+        }
+
+        // Shadow mask: how much of the light that could reach this side got blocked
+        if ndotl_raw > 0.0 && Some(closest.obj_id) != light.emitter {
+            shadow_total += intensity;
+            shadow_blocked += intensity * (1.0 - light_visibility);
+        }
+
+        if light_visibility > 0.0 {
+            // Diffuse (Lambert)
+            let cid = closest.obj_id;
+            let ndotl = ndotl_raw.max(0.0);
+            let diff = intensity * m.albedo * light_visibility * if Some(cid) != light.emitter {ndotl} else {ndotl_raw.abs()};
+
+            lr += br * lr_l * diff;
+            lg += bg * lg_l * diff;
+            lb += bb * lb_l * diff;
+
+            // Specular (Phong)
+            if m.specular_strength > 0.0 {
+                let reflect_dir = reflect(-l_dir, closest.normal).normalized();
+                let rv = reflect_dir.dot(view_dir).max(0.0);
+                let mut spec_factor = rv.powf(m.shininess) * m.specular_strength * intensity;
+                spec_factor *= light_visibility;
+
+                lr += lr_l * spec_factor;
+                lg += lg_l * spec_factor;
+                lb += lb_l * spec_factor;
+            }
+        }
+    }
+
+    (Vector3::new(lr, lg, lb), shadow_total, shadow_blocked)
+}
+
+/// Billboard particles glow on their own (unlit, tinted by their emission color).
+pub fn particle_glow(closest: &Hit, base: Vector3, scene: &Scene) -> Vector3 {
+    if closest.obj_id == ObjectId::PARTICLES || matches!(scene.get(closest.obj_id), Some(Object::Billboards(_))) {
+        let m = closest.material;
+        let (er, eg, eb) = srgb_to_linear(m.emission);
+        return Vector3::new(base.x * er, base.y * eg, base.z * eb) * m.emission_strength;
+    }
+    Vector3::zero()
+}
+
+// Base color (sRGB) of the surface at a hit: texture, animated atlas frame or flat diffuse
+pub fn surface_color(hit: &Hit, texmgr: &TextureManager, sky: &Sky) -> Color {
+    // Pick texture id (per-face or per-material, depending on your setup)
+    let tex_id = hit.tex_id.or(hit.material.texture);
+    let m = hit.material;
+
+    if let Some(ch) = tex_id {
+        let u = hit.uv.x;
+        let mut v = hit.uv.y;
+
+        // If this material is animated (frames stacked vertically)
+        if m.anim_frames > 1 && m.anim_fps > 0.0 {
+            // time in seconds
+            let t_anim = sky.elapsed; 
+
+            let frame_f = (t_anim * m.anim_fps).floor();
+            let frame_idx = (frame_f as u32) % m.anim_frames;
+
+            let frame_h = 1.0 / m.anim_frames as f32;
+
+            // v in [0,1] inside the frame → shift into the atlas
+            v = v.clamp(0.0, 1.0);
+            v = frame_idx as f32 * frame_h + v * frame_h;
+        }
+
+        // diffuse tints the texture (white for blocks, the particle colour for lit billboards)
+        let c = texmgr.sample_uv_bilinear(ch, u, v);
+        let d = m.diffuse;
+        Color::new(
+            (c.r as u16 * d.r as u16 / 255) as u8,
+            (c.g as u16 * d.g as u16 / 255) as u8,
+            (c.b as u16 * d.b as u16 / 255) as u8,
+            c.a,
+        )
+    } else {
+        m.diffuse
+    }
+}
+
+/// What the last `Renderer::render` cost.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RenderStats {
+    pub rays: u64,      // every ray traced: camera, reflection, refraction, shadow, AO
+    pub time: Duration, // tracing + denoising
+    pub reused: u64,    // pixels taken from the temporal cache instead of traced
+    pub pixels: u64,
+    pub cancelled: bool, // stopped by the `render_progressive` callback before the last tile
+}
+
+impl RenderStats {
+    pub fn rays_per_second(&self) -> f64 {
+        let secs = self.time.as_secs_f64();
+        if secs > 0.0 { self.rays as f64 / secs } else { 0.0 }
+    }
+
+    /// Fraction of the frame that came from the temporal cache.
+    pub fn reuse_ratio(&self) -> f32 {
+        if self.pixels > 0 { self.reused as f32 / self.pixels as f32 } else { 0.0 }
+    }
+}
+
+/// Traces scenes into a framebuffer; keeps what must survive between frames (the denoiser history
+/// and the temporal cache), the integrator that shades each ray and the stats of the last frame.
+pub struct Renderer {
+    pub denoiser: Denoiser,
+    pub integrator: Box<dyn Integrator>,
+    pub temporal: TemporalCache, // off by default: offline renders trace every pixel
+    pub tile_order: TileOrder,
+    pub tile_size: u32, // px
+    pub stats: RenderStats,
+    preview: Framebuffer, // what `render_progressive` callbacks see, updated tile by tile
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Renderer {
+    pub fn new() -> Self {
+        Renderer {
+            denoiser: Denoiser::new(),
+            integrator: Box::new(Whitted),
+            temporal: TemporalCache::new(),
+            tile_order: TileOrder::Spiral,
+            tile_size: 32,
+            stats: RenderStats::default(),
+            preview: Framebuffer::new(0, 0, Color::BLACK),
+        }
+    }
+
+    /// Renders one frame of `scene` seen from `camera`. With `aovs`, the passes of the pinhole ray are kept there.
+    pub fn render(&mut self, framebuffer: &mut Framebuffer, scene: &Scene, camera: &Camera, aovs: Option<&mut AovBuffer>) {
+        self.render_progressive(framebuffer, scene, camera, aovs, |_, _| true);
+    }
+
+    /// Like `render`, but calls `on_progress` on this thread each time a tile is finished, with a
+    /// copy of the frame so far (finished tiles are in `pixels`, the rest is what was there before).
+    /// Returning false cancels the frame: the remaining tiles are skipped, the denoiser and the
+    /// temporal cache keep the previous frame, and this returns false.
+    pub fn render_progressive(
+        &mut self,
+        framebuffer: &mut Framebuffer,
+        scene: &Scene,
+        camera: &Camera,
+        aovs: Option<&mut AovBuffer>,
+        mut on_progress: impl FnMut(&Framebuffer, Progress) -> bool,
+    ) -> bool {
+        let start = Instant::now();
+        let (rays, reused, finished) = self.trace_frame(framebuffer, scene, camera, aovs, &mut on_progress);
+        let pixels = framebuffer.width as u64 * framebuffer.height as u64;
+        self.stats = RenderStats { rays, time: start.elapsed(), reused, pixels, cancelled: !finished };
+        finished
+    }
+
+    /// Renders every frame of `path` to `out_dir/frame_0000.png`, `frame_0001.png`, ...
+    /// The scene is stepped with the clock's fixed timestep, so a sequence is reproducible.
+    /// `on_frame` gets each written file and how far the sequence is (counted in frames, not tiles).
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_sequence(
+        &mut self,
+        framebuffer: &mut Framebuffer,
+        scene: &mut Scene,
+        camera: &mut Camera,
+        path: &CameraPath,
+        clock: &FrameClock,
+        out_dir: &str,
+        mut on_frame: impl FnMut(&str, Progress),
+    ) -> std::io::Result<()> {
+        std::fs::create_dir_all(out_dir)?;
+        let dt = clock.dt();
+
+        let started = Instant::now();
+        for (i, t) in clock.frames() {
+            if let Some(pose) = path.sample(t) {
+                pose.apply(camera);
+            }
+            if camera.autofocus { camera.focus_on_center(scene); }
+            // Motion blur: the camera follows the path while the shutter is open
+            if camera.shutter > 0.0 {
+                let keys: Vec<(f32, Vector3, Vector3)> = (0..MAX_MOTION_KEYS)
+                    .filter_map(|k| {
+                        let dt_k = camera.shutter * k as f32 / (MAX_MOTION_KEYS - 1) as f32;
+                        path.sample(t + dt_k).map(|p| (dt_k, p.eye - camera.eye, p.center - camera.center))
+                    })
+                    .collect();
+                let eye_keys: Vec<(f32, Vector3)> = keys.iter().map(|k| (k.0, k.1)).collect();
+                let center_keys: Vec<(f32, Vector3)> = keys.iter().map(|k| (k.0, k.2)).collect();
+                camera.eye_motion = Motion::keyframed(&eye_keys);
+                camera.center_motion = Motion::keyframed(&center_keys);
+            }
+
+            // advance the world by exactly one frame (the first frame shows the initial state);
+            // offline frames always trace the particles, so they show up in glass and shadows
+            scene.raytraced_particles = true;
+            scene.update(if i == 0 { 0.0 } else { dt }, camera);
+
+            framebuffer.clear();
+            self.render(framebuffer, scene, camera, None);
+
+            let file = format!("{}/frame_{:04}.png", out_dir, i);
+            framebuffer.render_to_file(&file);
+            on_frame(&file, Progress { tiles_done: i + 1, tiles_total: clock.frame_count, elapsed: started.elapsed() });
+        }
+        Ok(())
+    }
+}
+
+impl Renderer {
+    // Traces the tiles in parallel, then denoises and resolves; returns (rays, reused pixels, finished)
+    fn trace_frame(
+        &mut self,
+        framebuffer: &mut Framebuffer,
+        scene: &Scene,
+        camera: &Camera,
+        aovs: Option<&mut AovBuffer>,
+        on_progress: &mut dyn FnMut(&Framebuffer, Progress) -> bool,
+    ) -> (u64, u64, bool) {
+        let Renderer { denoiser, integrator, temporal: cache, tile_order, tile_size, preview, .. } = self;
+        let integrator = integrator.as_ref();
+        let (width, height) = (framebuffer.width, framebuffer.height);
+
+        let denoise = denoiser.mode != DenoiseMode::Off && integrator.denoise();
+        // AOVs of the pinhole ray: asked for by the caller or needed to guide the denoiser
+        let want_aovs = denoise || aovs.is_some();
+        // Reuse last frame's shading where it still matches (not for AOV captures: every pass is fresh)
+        let reuse = cache.begin_frame(scene, integrator, camera) && aovs.is_none();
+        let mut local_aovs = AovBuffer::new(0, 0);
+        let gbuffer = aovs.unwrap_or(&mut local_aovs);
+        gbuffer.reset(width as usize, height as usize);
+        let n = (width * height) as usize;
+        framebuffer.linear.resize(n, Vector3::zero());
+        framebuffer.depth.resize(n, f32::INFINITY);
+
+        // 1) Rayon workers trace tiles into thread-local scratch and copy them into the framebuffer,
+        //    while this thread reports progress as they come in (and may cancel the rest)
+        let ctx = FrameContext {
+            scene,
+            camera,
+            integrator,
+            cache: &*cache,
+            reuse,
+            want_aovs,
+            width: width as f32,
+            height: height as f32,
+        };
+        let schedule = tiles(width, height, *tile_size, *tile_order);
+        let total = schedule.len();
+        let rays = AtomicU64::new(0);
+        let reused = AtomicU64::new(0);
+        let cancel = AtomicBool::new(false);
+        // the callback gets its own copy of the frame, so the lock workers wait on is only held
+        // for memcpys (never while the callback runs, which may use rayon itself)
+        preview.resize(width, height);
+        preview.pixels.copy_from_slice(&framebuffer.pixels);
+        preview.depth.copy_from_slice(&framebuffer.depth);
+        let output = Mutex::new((&mut *framebuffer, &mut *gbuffer));
+        let start = Instant::now();
+        let (tx, rx) = mpsc::channel();
+
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                schedule.par_iter().for_each_with(tx, |tx, tile| {
+                    if cancel.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    SCRATCH.with_borrow_mut(|scratch| {
+                        let rays_before = rays_traced(); // a tile stays on one thread
+                        let tile_reused = trace_tile(&ctx, tile, scratch);
+                        rays.fetch_add(rays_traced() - rays_before, Ordering::Relaxed);
+                        reused.fetch_add(tile_reused, Ordering::Relaxed);
+                        let mut out = output.lock().unwrap();
+                        let (fb, gbuf) = &mut *out;
+                        scratch.copy_to(tile, fb, gbuf);
+                    });
+                    let _ = tx.send(*tile);
+                });
+            });
+
+            // ends when the workers are done and every sender is dropped
+            for (i, tile) in rx.into_iter().enumerate() {
+                if cancel.load(Ordering::Relaxed) {
+                    continue;
+                }
+                copy_tile(output.lock().unwrap().0, preview, &tile);
+                let progress = Progress { tiles_done: i + 1, tiles_total: total, elapsed: start.elapsed() };
+                if !on_progress(preview, progress) {
+                    cancel.store(true, Ordering::Relaxed);
+                }
+            }
+        });
+        let (framebuffer, gbuffer) = output.into_inner().unwrap();
+        let (rays, reused) = (rays.into_inner(), reused.into_inner());
+        if cancel.into_inner() {
+            return (rays, reused, false);
+        }
+
+        // raw traced color, before the denoiser touches it
+        cache.store(&framebuffer.linear, gbuffer, camera);
+
+        // 2) Denoise in linear space
+        if denoise {
+            denoiser.denoise(&mut framebuffer.linear, gbuffer, camera);
+        } else {
+            denoiser.reset();
+        }
+
+        // 3) Float → 8-bit pixels
+        framebuffer.resolve();
+        (rays, reused, true)
+    }
+}
+
+// -------- Tiles --------
+
+// What every pixel of a frame shares
+struct FrameContext<'a> {
+    scene: &'a Scene,
+    camera: &'a Camera,
+    integrator: &'a dyn Integrator,
+    cache: &'a TemporalCache,
+    reuse: bool,     // look pixels up in the temporal cache
+    want_aovs: bool, // trace a pinhole ray for the AOVs even with DOF / motion blur
+    width: f32,
+    height: f32,
+}
+
+// Per-thread buffers a tile is traced into before it is copied to the framebuffer;
+// kept between tiles and frames, so tracing doesn't allocate
+#[derive(Default)]
+struct TileScratch {
+    color: Vec<Vector3>,
+    depth: Vec<f32>,
+    aovs: Vec<AovSample>,
+}
+
+thread_local! {
+    static SCRATCH: RefCell<TileScratch> = RefCell::new(TileScratch::default());
+}
+
+impl TileScratch {
+    fn copy_to(&self, tile: &Tile, framebuffer: &mut Framebuffer, gbuffer: &mut AovBuffer) {
+        let (w, stride) = (tile.width as usize, framebuffer.width as usize);
+        for row in 0..tile.height as usize {
+            let src = row * w..(row + 1) * w;
+            let first = (tile.y as usize + row) * stride + tile.x as usize;
+            let dst = first..first + w;
+            framebuffer.linear[dst.clone()].copy_from_slice(&self.color[src.clone()]);
+            framebuffer.depth[dst.clone()].copy_from_slice(&self.depth[src.clone()]);
+            gbuffer.samples[dst.clone()].copy_from_slice(&self.aovs[src.clone()]);
+            // shown right away; the whole frame is resolved again after denoising
+            for (p, c) in framebuffer.pixels[dst].iter_mut().zip(&self.color[src]) {
+                *p = linear_to_srgb(c.x, c.y, c.z);
+            }
+        }
+    }
+}
+
+// Copies the finished pixels (and depth) of one tile between framebuffers of the same size
+fn copy_tile(src: &Framebuffer, dst: &mut Framebuffer, tile: &Tile) {
+    let (w, stride) = (tile.width as usize, src.width as usize);
+    for row in 0..tile.height as usize {
+        let first = (tile.y as usize + row) * stride + tile.x as usize;
+        let range = first..first + w;
+        dst.pixels[range.clone()].copy_from_slice(&src.pixels[range.clone()]);
+        dst.depth[range.clone()].copy_from_slice(&src.depth[range]);
+    }
+}
+
+// Traces every pixel of a tile into `scratch`; returns how many came from the temporal cache
+fn trace_tile(ctx: &FrameContext, tile: &Tile, scratch: &mut TileScratch) -> u64 {
+    let n = tile.len();
+    scratch.color.clear();
+    scratch.color.resize(n, Vector3::zero());
+    scratch.depth.clear();
+    scratch.depth.resize(n, f32::INFINITY);
+    scratch.aovs.clear();
+    scratch.aovs.resize(n, AovSample::sky());
+
+    let mut reused = 0;
+    for ty in 0..tile.height {
+        for tx in 0..tile.width {
+            let i = (ty * tile.width + tx) as usize;
+            let (color, cached) = trace_pixel(ctx, tile.x + tx, tile.y + ty, &mut scratch.aovs[i]);
+            reused += cached as u64;
+            scratch.color[i] = color;
+            let g = &scratch.aovs[i];
+            scratch.depth[i] = if g.is_sky() { f32::INFINITY } else { ctx.camera.view_depth(&g.position) };
+        }
+    }
+    reused
+}
+
+// Linear color of one pixel (and whether it came from the temporal cache); fills `aov` with its first hit
+fn trace_pixel(ctx: &FrameContext, x: u32, y: u32, aov: &mut AovSample) -> (Vector3, bool) {
+    let FrameContext { scene, camera, integrator, .. } = *ctx;
+    let aspect_ratio = ctx.width / ctx.height;
+    let screen_x = (2.0 * x as f32) / ctx.width - 1.0;
+    let screen_y = -(2.0 * y as f32) / ctx.height + 1.0;
+
+    if ctx.reuse
+        && !ctx.cache.due(x, y)
+        && let Some((color, sample)) = ctx.cache.lookup(scene, camera, screen_x, screen_y, aspect_ratio)
+    {
+        *aov = sample;
+        return (color, true);
+    }
+
+    // Samples per pixel: spread over the lens (depth of field) and the shutter (motion blur)
+    let lens_n = if camera.aperture > 0.0 { camera.lens_samples.max(1) } else { 1 };
+    let time_n = if camera.shutter > 0.0 { camera.time_samples.max(1) } else { 1 };
+    let n = lens_n.max(time_n);
+
+    // Pinhole ray: the only sample without DOF/motion blur, and the one AOVs are taken from
+    let pinhole = |aov: &mut AovSample| match camera.primary_ray(screen_x, screen_y, aspect_ratio, Vector2::zero()) {
+        Some((ro_world, rd_world)) => integrator.li(&ro_world, &rd_world, scene, 0.0, Some(aov)),
+        None => Vector3::zero(), // outside the fisheye circle
+    };
+
+    if n <= 1 {
+        return (pinhole(aov), false);
+    }
+    if ctx.want_aovs {
+        pinhole(aov);
+    }
+    // Stratified samples, rotated per pixel
+    let rot = pixel_hash(x, y);
+    let mut sum = Vector3::zero();
+    for i in 0..n {
+        let u1 = (i as f32 + 0.5) / n as f32;
+        let u2 = (i as f32 * 0.618_034 + rot) % 1.0;
+        let lens = if lens_n > 1 { camera.sample_aperture(u1, u2) } else { Vector2::zero() };
+        let time = camera.shutter * ((i as f32 + rot) / n as f32);
+
+        let cam = camera.at_time(time);
+        let Some((ro_world, rd_world)) = cam.primary_ray(screen_x, screen_y, aspect_ratio, lens) else {
+            break;
+        };
+        // without a pinhole pass the first sample still provides the depth
+        let sample_aov = if i == 0 && !ctx.want_aovs { Some(&mut *aov) } else { None };
+        sum += integrator.li(&ro_world, &rd_world, scene, time, sample_aov);
+    }
+    (sum / n as f32, false)
+}
+
+/// Writes the beauty pass, one PNG per AOV and a single multi-layer PAM with all of them,
+/// plus the float versions: a multi-layer OpenEXR (beauty + every pass) and a PFM of the beauty.
+/// Returns the paths written, beauty first.
+pub fn export_aovs(framebuffer: &Framebuffer, passes: &AovBuffer, out_dir: &str, prefix: &str) -> std::io::Result<Vec<String>> {
+    let passes_written = passes.export_all(out_dir, prefix)?;
+    let beauty = format!("{}/{}_beauty.png", out_dir, prefix);
+    framebuffer.render_to_file(&beauty);
+    let layers = format!("{}/{}_layers.pam", out_dir, prefix);
+    passes.export_layers(&Aov::ALL, &layers)?;
+
+    let exr = format!("{}/{}.exr", out_dir, prefix);
+    passes.export_exr(&Aov::ALL, Some(&framebuffer.linear), &exr, ExrPixelType::Half)?;
+    let pfm = format!("{}/{}_beauty.pfm", out_dir, prefix);
+    framebuffer.render_to_pfm(&pfm)?;
+
+    let mut written = vec![beauty];
+    written.extend(passes_written);
+    written.extend([layers, exr, pfm]);
+    Ok(written)
+}
+
+// Cheap per-pixel hash in [0,1), used to decorrelate sample patterns between pixels
+fn pixel_hash(x: u32, y: u32) -> f32 {
+    let mut h = x.wrapping_mul(0x27d4_eb2d) ^ y.wrapping_mul(0x1656_67b1);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2c1b_3c6d);
+    h ^= h >> 12;
+    (h & 0xffff) as f32 / 65536.0
+}
+
@@ -0,0 +1,190 @@
+// resolution.rs
+use raylib::prelude::*;
+use rayon::prelude::*;
+
+use crate::framebuffer::Framebuffer;
+
+// -------- Resolución dinámica --------
+
+const SCALE_STEP: f32 = 1.0 / 32.0; // internal sizes are snapped so they don't change every frame
+const DAMPING: f32 = 0.5;           // fraction of the correction applied per frame
+const EDGE_DEPTH: f32 = 0.05;       // relative depth change treated as an edge by `Upscale::EdgeAware`
+
+/// How the traced frame is stretched to the window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Upscale {
+    Bilinear,
+    /// Bilinear, but taps at a different depth than the nearest one are dropped,
+    /// so silhouettes stay sharp instead of bleeding into the background.
+    EdgeAware,
+}
+
+impl Upscale {
+    pub fn name(self) -> &'static str {
+        match self {
+            Upscale::Bilinear => "bilinear",
+            Upscale::EdgeAware => "edge-aware",
+        }
+    }
+}
+
+/// Frame-time controller for the viewer: while the camera moves the internal resolution drops
+/// until tracing fits in `target_ms`; once it stops, the resolution climbs back to `max_scale`.
+#[derive(Clone, Copy, Debug)]
+pub struct DynamicResolution {
+    pub enabled: bool,
+    pub target_ms: f32,   // trace budget per frame while moving
+    pub min_scale: f32,   // of the window size, per axis
+    pub max_scale: f32,
+    pub refine_rate: f32, // scale gained per still frame
+    pub filter: Upscale,
+    scale: f32,
+}
+
+impl Default for DynamicResolution {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DynamicResolution {
+    pub fn new() -> Self {
+        DynamicResolution {
+            enabled: true,
+            target_ms: 33.0,
+            min_scale: 0.25,
+            max_scale: 1.0,
+            refine_rate: 0.125,
+            filter: Upscale::EdgeAware,
+            scale: 1.0,
+        }
+    }
+
+    pub fn scale(&self) -> f32 {
+        if self.enabled { self.scale } else { 1.0 }
+    }
+
+    /// Full resolution → dynamic + bilinear → dynamic + edge-aware → full resolution.
+    pub fn cycle(&mut self) {
+        match (self.enabled, self.filter) {
+            (false, _) => (self.enabled, self.filter) = (true, Upscale::Bilinear),
+            (true, Upscale::Bilinear) => self.filter = Upscale::EdgeAware,
+            (true, Upscale::EdgeAware) => self.enabled = false,
+        }
+        self.scale = self.max_scale;
+    }
+
+    pub fn name(&self) -> String {
+        if self.enabled { format!("dynamic ({})", self.filter.name()) } else { "full".to_string() }
+    }
+
+    /// Feeds back how long the last frame took to trace at the current scale.
+    pub fn update(&mut self, trace_ms: f32, moving: bool) {
+        if !self.enabled {
+            return;
+        }
+        if moving {
+            // trace time grows with the pixel count, i.e. with scale²
+            if trace_ms > 0.0 {
+                let wanted = self.scale * (self.target_ms / trace_ms).sqrt();
+                self.scale += (wanted - self.scale) * DAMPING;
+            }
+        } else {
+            self.scale += self.refine_rate;
+        }
+        self.scale = self.scale.clamp(self.min_scale, self.max_scale);
+    }
+
+    /// Size to trace at for a window of `width`×`height`.
+    pub fn internal_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let s = ((self.scale() / SCALE_STEP).round() * SCALE_STEP).min(1.0);
+        let dim = |d: u32| ((d as f32 * s).round() as u32).clamp(1, d.max(1));
+        (dim(width), dim(height))
+    }
+}
+
+/// Stretches `src` over `dst` (pixels and depth) on the rayon pool. Same size is a plain copy.
+pub fn upscale(src: &Framebuffer, dst: &mut Framebuffer, filter: Upscale) {
+    if (src.width, src.height) == (dst.width, dst.height) {
+        dst.pixels.copy_from_slice(&src.pixels);
+        dst.depth.copy_from_slice(&src.depth);
+        return;
+    }
+    let (dw, dh) = (dst.width as usize, dst.height as usize);
+    dst.pixels
+        .par_chunks_mut(dw)
+        .zip(dst.depth.par_chunks_mut(dw))
+        .enumerate()
+        .for_each(|(y, (row, depth_row))| upscale_row(src, dh, y, row, depth_row, filter));
+}
+
+/// Like `upscale`, but on the calling thread: for presenting while the rayon pool is busy
+/// tracing tiles (a parallel upscale would queue behind them).
+pub fn upscale_sequential(src: &Framebuffer, dst: &mut Framebuffer, filter: Upscale) {
+    if (src.width, src.height) == (dst.width, dst.height) {
+        dst.pixels.copy_from_slice(&src.pixels);
+        dst.depth.copy_from_slice(&src.depth);
+        return;
+    }
+    let (dw, dh) = (dst.width as usize, dst.height as usize);
+    for (y, (row, depth_row)) in dst.pixels.chunks_mut(dw).zip(dst.depth.chunks_mut(dw)).enumerate() {
+        upscale_row(src, dh, y, row, depth_row, filter);
+    }
+}
+
+// Row `y` of a `dst_height`-row window; `row` and `depth_row` are the window width long
+fn upscale_row(src: &Framebuffer, dst_height: usize, y: usize, row: &mut [Color], depth_row: &mut [f32], filter: Upscale) {
+    let (sw, sh) = (src.width as usize, src.height as usize);
+    let (kx, ky) = (sw as f32 / row.len() as f32, sh as f32 / dst_height as f32);
+
+    // pixel centres of the window mapped onto the traced frame
+    let sy = ((y as f32 + 0.5) * ky - 0.5).clamp(0.0, (sh - 1) as f32);
+    let (y0, fy) = (sy.floor() as usize, sy.fract());
+    let y1 = (y0 + 1).min(sh - 1);
+    for (x, (pixel, depth)) in row.iter_mut().zip(depth_row.iter_mut()).enumerate() {
+        let sx = ((x as f32 + 0.5) * kx - 0.5).clamp(0.0, (sw - 1) as f32);
+        let (x0, fx) = (sx.floor() as usize, sx.fract());
+        let x1 = (x0 + 1).min(sw - 1);
+
+        let taps = [
+            (y0 * sw + x0, (1.0 - fx) * (1.0 - fy)),
+            (y0 * sw + x1, fx * (1.0 - fy)),
+            (y1 * sw + x0, (1.0 - fx) * fy),
+            (y1 * sw + x1, fx * fy),
+        ];
+        let (nearest, _) = taps.iter().copied().fold((taps[0].0, -1.0), |a, t| if t.1 > a.1 { t } else { a });
+        let d_ref = src.depth[nearest];
+
+        let (mut sum, mut sum_w) = ([0.0f32; 4], 0.0);
+        for (i, w) in taps {
+            let w = match filter {
+                Upscale::Bilinear => w,
+                Upscale::EdgeAware => w * depth_similarity(src.depth[i], d_ref),
+            };
+            let c = src.pixels[i];
+            for (s, v) in sum.iter_mut().zip([c.r, c.g, c.b, c.a]) {
+                *s += v as f32 * w;
+            }
+            sum_w += w;
+        }
+        *pixel = if sum_w > 1e-4 {
+            let [r, g, b, a] = sum.map(|s| (s / sum_w).round() as u8);
+            Color::new(r, g, b, a)
+        } else {
+            src.pixels[nearest]
+        };
+        *depth = d_ref;
+    }
+}
+
+// 1 for the same surface, falling to 0 across a depth edge (sky only matches sky)
+fn depth_similarity(d: f32, d_ref: f32) -> f32 {
+    match (d.is_finite(), d_ref.is_finite()) {
+        (false, false) => 1.0,
+        (true, true) => {
+            let rel = (d - d_ref).abs() / (d_ref.abs() * EDGE_DEPTH).max(1e-4);
+            (-rel * rel).exp()
+        }
+        _ => 0.0,
+    }
+}
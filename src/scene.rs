@@ -0,0 +1,419 @@
+// scene.rs
+use std::{cell::Cell, collections::HashMap};
+
+use raylib::prelude::*;
+
+use crate::{
+    bvh::Bvh,
+    camera::Camera,
+    light::{PointLight, build_lights_from_objects},
+    media::{Volume, sample_volumes},
+    object::{BillboardCloud, BillboardFacing, Object, sample_objects},
+    occlusion::{AoMode, VoxelGrid},
+    ray_intersect::Hit,
+    skybox::Sky,
+    sprites::SpriteSystem,
+    textures::TextureManager,
+    voxel_light::{LightGrid, block_of},
+};
+
+pub use crate::ray_intersect::ObjectId;
+
+thread_local! {
+    // BVH node + object tests done by this thread (for the heatmap integrator)
+    static INTERSECTION_TESTS: Cell<u32> = const { Cell::new(0) };
+    // rays this thread has traced, ever (for the HUD's rays per second)
+    static RAYS_TRACED: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Rays (camera, reflection, shadow, AO, ...) this thread has traced so far. Never reset:
+/// take the difference between two calls.
+pub fn rays_traced() -> u64 {
+    RAYS_TRACED.with(|c| c.get())
+}
+
+/// Intersection tests (BVH nodes + objects) this thread has done since the last `reset_intersection_tests`.
+pub fn intersection_tests() -> u32 {
+    INTERSECTION_TESTS.with(|c| c.get())
+}
+
+pub fn reset_intersection_tests() {
+    INTERSECTION_TESTS.with(|c| c.set(0));
+}
+
+fn count_tests(n: u32) {
+    INTERSECTION_TESTS.with(|c| c.set(c.get().saturating_add(n)));
+}
+
+/// Everything a frame needs: objects (with stable ids), their lights, media, textures, sky and particles,
+/// plus a BVH over the objects. Call `update` once per frame before rendering.
+pub struct Scene {
+    objects: Vec<Object>, // dense, so tracing walks a plain slice
+    ids: Vec<ObjectId>,   // id of each entry of `objects`
+    slots: HashMap<ObjectId, usize>,
+    next_id: u32,
+    revision: u64, // bumped on every add/remove/replace, so caches know the geometry changed
+
+    pub volumes: Vec<Volume>,
+    pub textures: TextureManager,
+    pub sky: Sky,
+    pub sprites: SpriteSystem,
+    pub raytraced_particles: bool, // particles traced as billboards (reflections, shadows) instead of composited
+    pub ao: AoMode,                // occlusion of the sky ambient term
+    pub voxel_lighting: bool,      // lamp blocks light the scene through `LightGrid` levels instead of shadow rays
+
+    object_lights: Vec<PointLight>, // from emissive objects, rebuilt when objects change
+    lights: Vec<PointLight>,        // object lights + sun/moon + particle lights, rebuilt every update
+    particles: Option<BillboardCloud>,
+    voxels: VoxelGrid, // opaque unit blocks, for voxel-corner AO
+    light_grid: LightGrid,
+
+    // Acceleration data: BVH over bounded objects; unbounded ones are always tested
+    bvh: Bvh,
+    unbounded: Vec<usize>,
+}
+
+impl Scene {
+    pub fn new(objects: Vec<Object>, volumes: Vec<Volume>, textures: TextureManager) -> Self {
+        let mut scene = Scene {
+            objects: Vec::new(),
+            ids: Vec::new(),
+            slots: HashMap::new(),
+            next_id: 0,
+            revision: 0,
+            volumes,
+            textures,
+            sky: Sky::new(),
+            sprites: SpriteSystem::default(),
+            raytraced_particles: true,
+            ao: AoMode::VoxelCorners,
+            voxel_lighting: false,
+            object_lights: Vec::new(),
+            lights: Vec::new(),
+            particles: None,
+            voxels: VoxelGrid::default(),
+            light_grid: LightGrid::default(),
+            bvh: Bvh::default(),
+            unbounded: Vec::new(),
+        };
+        for obj in objects {
+            scene.push(obj);
+        }
+        scene.light_grid = LightGrid::from_objects(scene.objects());
+        scene.rebuild();
+        scene
+    }
+
+    /// The sample diorama: blocks, lamps, the nether portal, the OBJ model and its fog.
+    pub fn diorama(textures: TextureManager) -> Self {
+        Scene::new(sample_objects(), sample_volumes(), textures)
+    }
+
+    // -------- Objects --------
+
+    fn push(&mut self, obj: Object) -> ObjectId {
+        let id = ObjectId(self.next_id);
+        self.next_id += 1;
+        self.slots.insert(id, self.objects.len());
+        self.objects.push(obj);
+        self.ids.push(id);
+        id
+    }
+
+    /// Adds an object; its id stays valid until it is removed.
+    pub fn add(&mut self, obj: Object) -> ObjectId {
+        let block = block_of(&obj);
+        let id = self.push(obj);
+        self.relight(None, block);
+        self.rebuild();
+        id
+    }
+
+    fn take(&mut self, id: ObjectId) -> Option<Object> {
+        let slot = self.slots.remove(&id)?;
+        let obj = self.objects.swap_remove(slot);
+        self.ids.swap_remove(slot);
+        if let Some(moved) = self.ids.get(slot) {
+            self.slots.insert(*moved, slot);
+        }
+        Some(obj)
+    }
+
+    pub fn remove(&mut self, id: ObjectId) -> Option<Object> {
+        let obj = self.take(id)?;
+        self.relight(block_of(&obj), None);
+        self.rebuild();
+        Some(obj)
+    }
+
+    /// Removes `remove` and adds `add` in one go: the light grid and the BVH are rebuilt once
+    /// instead of per object (loading a whole build). Returns the ids of the added objects.
+    pub fn replace_many(&mut self, remove: impl IntoIterator<Item = ObjectId>, add: impl IntoIterator<Item = Object>) -> Vec<ObjectId> {
+        for id in remove {
+            self.take(id);
+        }
+        let ids = add.into_iter().map(|obj| self.push(obj)).collect();
+        self.light_grid = LightGrid::from_objects(self.objects());
+        self.rebuild();
+        ids
+    }
+
+    /// Swaps the object behind `id` for another one, keeping the id. Returns the old object.
+    pub fn replace(&mut self, id: ObjectId, obj: Object) -> Option<Object> {
+        let slot = *self.slots.get(&id)?;
+        let block = block_of(&obj);
+        let old = std::mem::replace(&mut self.objects[slot], obj);
+        self.relight(block_of(&old), block);
+        self.rebuild();
+        Some(old)
+    }
+
+    pub fn get(&self, id: ObjectId) -> Option<&Object> {
+        self.slots.get(&id).map(|&slot| &self.objects[slot])
+    }
+
+    pub fn contains(&self, id: ObjectId) -> bool {
+        self.slots.contains_key(&id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// Changes every time an object is added, removed or replaced.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Triangles the objects would have as meshes (cubes 12, quads 2, spheres 0).
+    pub fn triangle_count(&self) -> usize {
+        self.objects.iter().map(Object::triangle_count).sum()
+    }
+
+    pub fn objects(&self) -> impl Iterator<Item = (ObjectId, &Object)> {
+        self.ids.iter().copied().zip(self.objects.iter())
+    }
+
+    pub fn particles(&self) -> Option<&BillboardCloud> {
+        self.particles.as_ref()
+    }
+
+    pub fn voxels(&self) -> &VoxelGrid {
+        &self.voxels
+    }
+
+    pub fn light_grid(&self) -> &LightGrid {
+        &self.light_grid
+    }
+
+    // -------- Lights --------
+
+    /// Lights to trace this frame.
+    pub fn lights(&self) -> &[PointLight] {
+        &self.lights
+    }
+
+    /// Ambient light at a hit: the sky's ambient term, or block + sky light levels with `voxel_lighting`.
+    pub fn ambient_light(&self, hit: &Hit) -> Vector3 {
+        if self.voxel_lighting {
+            self.light_grid.ambient(hit.point + hit.normal * 0.5, self.sky.ambient)
+        } else {
+            Vector3::one() * self.sky.ambient
+        }
+    }
+
+    fn refresh_lights(&mut self) {
+        self.lights.clear();
+        for light in &self.object_lights {
+            // with voxel lighting, lamp blocks only light through their light levels
+            let lamp_block = light.emitter.and_then(|id| self.get(id)).and_then(block_of).is_some_and(|(.., e)| e > 0);
+            if !(self.voxel_lighting && lamp_block) {
+                self.lights.push(*light);
+            }
+        }
+        self.lights.push(self.sky.sun);
+        self.lights.push(self.sky.moon);
+        if let Some(cloud) = &self.particles {
+            // a few short-range lights so the particles glow onto nearby blocks
+            self.lights.extend(cloud.lights(ObjectId::PARTICLES, 4, 0.15, 3.0));
+        }
+    }
+
+    // -------- Per-frame update --------
+
+    /// Steps the sky and the particles by `dt`, rebuilds the particle billboards for `camera`
+    /// and the list of lights.
+    pub fn update(&mut self, dt: f32, camera: &Camera) {
+        self.sky.update_sky(dt);
+
+        // sprites collide against the scene they belong to
+        let mut sprites = std::mem::take(&mut self.sprites);
+        sprites.update(dt, self);
+        self.sprites = sprites;
+
+        self.particles = (self.raytraced_particles && !self.sprites.sprites.is_empty()).then(|| {
+            BillboardCloud::build(&self.sprites.sprites, camera, &self.textures, BillboardFacing::Camera, camera.shutter)
+        });
+        self.refresh_lights();
+    }
+
+    /// Runs the particles for `seconds` so the first frame doesn't start empty.
+    pub fn prewarm_particles(&mut self, seconds: f32) {
+        let mut sprites = std::mem::take(&mut self.sprites);
+        sprites.prewarm(seconds, self);
+        self.sprites = sprites;
+    }
+
+    // -------- Intersection --------
+
+    /// Closest hit along the ray, particles included.
+    pub fn intersect(&self, ro: &Vector3, rd: &Vector3, time: f32) -> Hit {
+        let mut closest = self.intersect_objects(ro, rd, time);
+        if let Some(cloud) = &self.particles {
+            count_tests(1);
+            let h = cloud.ray_intersect_at(ro, rd, ObjectId::PARTICLES, time);
+            if h.is_intersecting && h.distance < closest.distance {
+                closest = h;
+            }
+        }
+        closest
+    }
+
+    /// Closest hit along the ray against the objects only (no particles).
+    pub fn intersect_objects(&self, ro: &Vector3, rd: &Vector3, time: f32) -> Hit {
+        let mut closest = Hit::no_hit();
+        self.visit(ro, rd, f32::INFINITY, |id, obj| {
+            let h = obj.ray_intersect_at(ro, rd, id, time);
+            if h.is_intersecting && h.distance < closest.distance {
+                closest = h;
+            }
+            true
+        });
+        closest
+    }
+
+    /// Calls `f` for every object whose bounds the ray may cross before `max_t`;
+    /// `f` returns false to stop early.
+    pub fn visit(&self, ro: &Vector3, rd: &Vector3, max_t: f32, mut f: impl FnMut(ObjectId, &Object) -> bool) {
+        let mut tests = 0;
+        self.visit_counted(ro, rd, max_t, &mut tests, &mut f);
+        count_tests(tests);
+        RAYS_TRACED.with(|c| c.set(c.get() + 1));
+    }
+
+    fn visit_counted(
+        &self,
+        ro: &Vector3,
+        rd: &Vector3,
+        max_t: f32,
+        tests: &mut u32,
+        f: &mut impl FnMut(ObjectId, &Object) -> bool,
+    ) {
+        for &i in &self.unbounded {
+            *tests += 1;
+            if !f(self.ids[i], &self.objects[i]) {
+                return;
+            }
+        }
+        let mut object_tests = 0;
+        let node_tests = self.bvh.visit(ro, rd, max_t, |i, _| {
+            object_tests += 1;
+            f(self.ids[i], &self.objects[i])
+        });
+        *tests += node_tests + object_tests;
+    }
+
+    // -------- Acceleration data --------
+
+    // Light levels of the blocks an edit touches: the old one goes dark, the new one is placed
+    // (called after the object list changed)
+    fn relight(&mut self, old: Option<([i32; 3], bool, u8)>, new: Option<([i32; 3], bool, u8)>) {
+        let mut in_grid = true;
+        if let Some((cell, ..)) = old {
+            in_grid &= self.light_grid.set_block(cell, false, 0);
+        }
+        if let Some((cell, opaque, emission)) = new {
+            in_grid &= self.light_grid.set_block(cell, opaque, emission);
+        }
+        if !in_grid {
+            // edits outside the grid grow it
+            self.light_grid = LightGrid::from_objects(self.objects());
+        }
+    }
+
+    // Lights, voxel grid and BVH follow the object list
+    fn rebuild(&mut self) {
+        self.revision += 1;
+        self.object_lights = build_lights_from_objects(self.objects());
+        self.refresh_lights();
+        self.voxels = VoxelGrid::from_objects(self.objects());
+
+        self.unbounded.clear();
+        let mut bounded = Vec::with_capacity(self.objects.len());
+        for (i, obj) in self.objects.iter().enumerate() {
+            match obj.bounds() {
+                Some(b) => bounded.push((i, b)),
+                None => self.unbounded.push(i),
+            }
+        }
+        self.bvh = Bvh::build(bounded);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::Cube;
+
+    fn cube_at(x: f32) -> Object {
+        Object::Cube(Cube::new(x, 0.0, 0.0, '+'))
+    }
+
+    fn x_of(scene: &Scene, id: ObjectId) -> Option<f32> {
+        match scene.get(id)? {
+            Object::Cube(c) => Some(c.min.x),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn ids_survive_removing_other_objects() {
+        let mut scene = Scene::new(Vec::new(), Vec::new(), TextureManager::empty());
+        let ids: Vec<ObjectId> = (0..5).map(|x| scene.add(cube_at(x as f32))).collect();
+
+        // removing from the middle moves the last object into the hole; its id must follow it
+        assert!(scene.remove(ids[1]).is_some());
+        assert!(scene.remove(ids[1]).is_none());
+        assert!(!scene.contains(ids[1]));
+        for (x, &id) in ids.iter().enumerate().filter(|&(x, _)| x != 1) {
+            assert_eq!(x_of(&scene, id), Some(x as f32));
+        }
+
+        // replace keeps the id
+        scene.replace(ids[4], cube_at(10.0));
+        assert_eq!(x_of(&scene, ids[4]), Some(10.0));
+    }
+
+    #[test]
+    fn replace_many_keeps_the_ids_it_doesnt_touch() {
+        let mut scene = Scene::new(Vec::new(), Vec::new(), TextureManager::empty());
+        let ids: Vec<ObjectId> = (0..4).map(|x| scene.add(cube_at(x as f32))).collect();
+
+        let added = scene.replace_many([ids[0], ids[2]], [cube_at(7.0), cube_at(8.0)]);
+        assert_eq!(scene.len(), 4);
+        assert!(!scene.contains(ids[0]) && !scene.contains(ids[2]));
+        assert_eq!(x_of(&scene, ids[1]), Some(1.0));
+        assert_eq!(x_of(&scene, ids[3]), Some(3.0));
+        assert_eq!(added.iter().map(|&id| x_of(&scene, id)).collect::<Vec<_>>(), [Some(7.0), Some(8.0)]);
+
+        // new ids are never reused ones
+        assert!(added.iter().all(|id| !ids.contains(id)));
+        // and the BVH follows: a ray down the row hits the nearest cube
+        let hit = scene.intersect_objects(&Vector3::new(-5.0, 0.5, 0.5), &Vector3::new(1.0, 0.0, 0.0), 0.0);
+        assert_eq!(hit.obj_id, ids[1]);
+    }
+}
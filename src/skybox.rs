@@ -3,7 +3,7 @@ use std::f32::consts::PI;
 // sky.rs
 use raylib::prelude::*;
 
-use crate::{color::lerp_color, light::PointLight};
+use crate::{clouds::CloudLayer, color::lerp_color, light::PointLight, media::Fog};
 
 pub struct Sky {
     pub time: f32,           // 0..1 = fraction of the day
@@ -12,6 +12,14 @@ pub struct Sky {
     pub sun: PointLight,
     pub moon: PointLight,
     pub ambient: f32,
+    pub clouds: CloudLayer,
+    pub fog: Fog,
+}
+
+impl Default for Sky {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Sky {
@@ -20,7 +28,13 @@ impl Sky {
         let day_length = 20.0_f32;          // seconds per full day/night cycle
         let sun = PointLight::new(Vector3::new(-1000.0, 0.0, 0.0), 0.0, Color::BLACK, None);
         let moon = PointLight::new(Vector3::new(1000.0, 0.0, 0.0), 0.0, Color::new(200, 210, 255, 255), None);
-        Sky {time, elapsed: 0.0, day_length, sun, moon, ambient: 0.0}
+        Sky {time, elapsed: 0.0, day_length, sun, moon, ambient: 0.0, clouds: CloudLayer::new(), fog: Fog::new()}
+    }
+
+    /// Time of day as (hours, minutes): `time` 0 is sunrise at 06:00, 0.25 noon.
+    pub fn clock(&self) -> (u32, u32) {
+        let minutes = ((self.time * 24.0 + 6.0) * 60.0) as u32 % (24 * 60);
+        (minutes / 60, minutes % 60)
     }
 
     pub fn update_sky(&mut self, dt: f32) {
@@ -66,6 +80,16 @@ impl Sky {
     }
 
     pub fn procedural_sky(&self, dir: Vector3) -> Vector3 {
+        self.sky_color(dir, true)
+    }
+
+    /// The sky without the sun and moon discs: for paths that already sampled them as lights,
+    /// so they aren't counted twice.
+    pub fn procedural_sky_without_discs(&self, dir: Vector3) -> Vector3 {
+        self.sky_color(dir, false)
+    }
+
+    fn sky_color(&self, dir: Vector3, discs: bool) -> Vector3 {
         let d = dir.normalized();
         let t = (d.y + 1.0) * 0.5; // map y [-1,1] → [0,1]
 
@@ -121,6 +145,7 @@ impl Sky {
         let night_factor = 1.0 - day_factor;
 
         let mut color = day_color * day_factor + night_color * night_factor;
+        let base_color = color;
 
         // ---------- 4) SUN DISC ----------
         // Use angular distance via vector difference (no acos needed).
@@ -131,16 +156,14 @@ impl Sky {
         let sun_outer = 0.12; // edge of sun glow
         let sun_inner = 0.012; // crisp core
 
-        let sun_intensity = if sun_visibility > 0.0 {
+        let sun_intensity = if discs && sun_visibility > 0.0 {
             // Map dist2 to [0,1] disc factor using smoothstep
             let dist = sun_dist2.sqrt();
             let core = 1.0 - smoothstep(0.0, sun_inner, dist);   // bright center
             let halo = 1.0 - smoothstep(sun_inner, sun_outer, dist); // soft falloff
 
             // Sun brightness scales with visibility (higher when high in the sky)
-            let brightness = (core * 2.0 + halo) * sun_visibility;
-
-            brightness
+            (core * 2.0 + halo) * sun_visibility
         } else {
             0.0
         };
@@ -157,13 +180,12 @@ impl Sky {
         let moon_outer = 0.048;
         let moon_inner = 0.012;
 
-        let moon_intensity = if moon_visibility > 0.0 {
+        let moon_intensity = if discs && moon_visibility > 0.0 {
             let dist = moon_dist2.sqrt();
             let core = 1.0 - smoothstep(0.0, moon_inner, dist);
             let halo = 1.0 - smoothstep(moon_inner, moon_outer, dist);
 
-            let brightness = (core * 1.5 + halo * 0.8) * moon_visibility * 0.8;
-            brightness
+            (core * 1.5 + halo * 0.8) * moon_visibility * 0.8
         } else {
             0.0
         };
@@ -174,6 +196,19 @@ impl Sky {
             color += moon_col * moon_intensity;
         }
 
+        // ---------- 6) CLOUDS ----------
+        // The diorama is tiny compared to the cloud layer, so march from the origin.
+        let cloud_ambient = base_color * 0.5 + Vector3::one() * self.ambient;
+        let (cloud_col, cloud_t) = self.clouds.march(
+            Vector3::zero(),
+            d,
+            self.elapsed,
+            &self.sun,
+            &self.moon,
+            cloud_ambient,
+        );
+        color = color * cloud_t + cloud_col;
+
         // Clamp to [0,1] so we don't blow out
         Vector3::new(
             color.x.clamp(0.0, 1.0),
@@ -181,6 +216,23 @@ impl Sky {
             color.z.clamp(0.0, 1.0),
         )
     }
+
+    /// Fog takes the color of the sky at the horizon in the direction we are looking.
+    pub fn fog_color(&self, dir: Vector3) -> Vector3 {
+        let flat = Vector3::new(dir.x, 0.0, dir.z);
+        if flat.length() < 1e-4 {
+            return self.procedural_sky(Vector3::new(1.0, 0.0, 0.0));
+        }
+        self.procedural_sky(flat)
+    }
+
+    /// How much direct sun/moon light reaches `p` along `dir` after passing through the clouds.
+    pub fn cloud_shadow(&self, p: Vector3, dir: Vector3) -> f32 {
+        if dir.y <= 0.0 {
+            return 1.0;
+        }
+        self.clouds.transmittance(p, dir, self.elapsed, self.clouds.light_steps * 2)
+    }
 }
 
 fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
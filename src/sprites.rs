@@ -1,7 +1,9 @@
 // sprites.rs
 use raylib::prelude::*;
-use rand::Rng;
-use crate::{camera::Camera, object::Object, ray_intersect::{Hit, RayIntersect}};
+use crate::camera::Camera;
+use crate::emitter::{Collision, Emitter};
+use crate::scene::Scene;
+use crate::ray_intersect::Hit;
 use crate::{framebuffer::Framebuffer, textures::TextureManager};
 
 #[derive(Clone, Copy, Debug)]
@@ -12,213 +14,247 @@ pub struct Sprite {
     pub age: f32,
     pub lifetime: f32,
     pub tex_id: char,      // which texture in TextureManager to use
+    pub tint: Color,       // multiplies the texture; its alpha scales the sprite's opacity
     pub visible: bool,
+    pub emitter: usize,    // index into `SpriteSystem::emitters`
+    pub base_size: f32,    // size before the emitter's size-over-life curve
+    pub phase: f32,        // random in [0,1): sprite-sheet start and turbulence offset
+    pub emission: f32,     // glow when raytraced, copied from the emitter
+    pub stuck: bool,       // landed on geometry with `Collision::Stick`
 }
 
+/// Particles from any number of emitters, simulated together.
 pub struct SpriteSystem {
     pub sprites: Vec<Sprite>,
-    pub region_min: Vector3,
-    pub region_max: Vector3,
+    pub emitters: Vec<Emitter>,
+    time: f32,
+}
+
+impl Default for SpriteSystem {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
 }
 
 impl SpriteSystem {
-    pub fn new(region_min: Vector3, region_max: Vector3) -> Self {
-        let mut sprites = Vec::new();
-        let mut rng = rand::thread_rng();
-        for _ in 0..25 {
-            let pid = rng.gen_range(0..=7);
-            sprites.push(respawn(region_min, region_max, match pid {
-                0 => '0',
-                1 => '1',
-                2 => '2',
-                3 => '3',
-                4 => '4',
-                5 => '5',
-                6 => '6',
-                7 => '7',
-                _ => '0'
-            }));
-        }
+    pub fn new(emitters: Vec<Emitter>) -> Self {
         Self {
-            sprites,
-            region_min,
-            region_max,
+            sprites: Vec::new(),
+            emitters,
+            time: 0.0,
         }
     }
 
-    pub fn update(&mut self, dt: f32, camera: &Camera, objects: &[Object]) {
-        for s in &mut self.sprites {
+    /// Runs the simulation for `seconds` so the first frame doesn't start empty.
+    pub fn prewarm(&mut self, seconds: f32, scene: &Scene) {
+        let step = 1.0 / 30.0;
+        let mut t = 0.0;
+        while t < seconds {
+            self.update(step, scene);
+            t += step;
+        }
+    }
+
+    pub fn emitter_mut(&mut self, name: &str) -> Option<&mut Emitter> {
+        self.emitters.iter_mut().find(|e| e.name == name)
+    }
+
+    /// Steps every particle by `dt`; emitters with a `Collision` test their motion against the scene's objects.
+    pub fn update(&mut self, dt: f32, scene: &Scene) {
+        self.time += dt;
+        let time = self.time;
+        let emitters = &self.emitters;
+
+        // Age and kill
+        self.sprites.retain_mut(|s| {
             s.age += dt;
-            if s.age > s.lifetime {
-                // respawn as a new random sprite
-                *s = respawn(self.region_min, self.region_max, s.tex_id);
+            s.age < s.lifetime
+        });
+
+        // Forces: gravity + turbulence, then drag (exact decay, stable for any dt)
+        for s in &mut self.sprites {
+            if s.stuck {
                 continue;
             }
+            let e = &emitters[s.emitter];
+            s.velocity += e.acceleration(s.position, time, s.phase) * dt;
+            s.velocity *= (-e.drag * dt).exp();
+            let step = s.velocity * dt;
+            // only colliding emitters pay for the raycast
+            let hit = if e.collision == Collision::None { None } else { first_hit(scene, s.position, step) };
+            match hit {
+                Some(hit) => collide(s, e.collision, &hit),
+                None => s.position += step,
+            }
+        }
+        self.sprites.retain(|s| s.lifetime > 0.0 && !emitters[s.emitter].out_of_bounds(s.position));
 
-            s.position += s.velocity * dt;
-
-            // Optionally keep inside region (bounce or wrap)
-            if s.position.x < self.region_min.x || s.position.x > self.region_max.x
-                || s.position.y < self.region_min.y || s.position.y > self.region_max.y
-                || s.position.z < self.region_min.z || s.position.z > self.region_max.z
-            {
-                *s = respawn(self.region_min, self.region_max, s.tex_id);
+        // Spawn
+        let mut rng = rand::thread_rng();
+        for (i, e) in self.emitters.iter_mut().enumerate() {
+            let live = self.sprites.iter().filter(|s| s.emitter == i).count();
+            for _ in 0..e.spawn_count(dt, live) {
+                let (position, velocity, lifetime, base_size, phase) = e.spawn(&mut rng);
+                self.sprites.push(Sprite {
+                    position,
+                    velocity,
+                    size_world: base_size,
+                    age: 0.0,
+                    lifetime,
+                    tex_id: e.sheet.frames.first().copied().unwrap_or('0'),
+                    tint: Color::WHITE,
+                    visible: true,
+                    emitter: i,
+                    base_size,
+                    phase,
+                    emission: e.emission,
+                    stuck: false,
+                });
             }
+        }
 
-            // Update visibility based on occlusion
-            s.visible = sprite_visible(camera, s.position, objects);
+        // Size, colour and sprite-sheet frame over the lifetime
+        for s in &mut self.sprites {
+            let e = &self.emitters[s.emitter];
+            let t = s.age / s.lifetime;
+            s.size_world = s.base_size * e.size_at(t);
+            s.tint = e.color_at(t);
+            s.tex_id = e.sheet.frame(s.age, s.lifetime, s.phase);
         }
     }
 }
 
-fn sprite_visible(camera: &Camera, sprite_pos: Vector3, objects: &[Object]) -> bool {
-        let ro = camera.eye;
-        let to_sprite = sprite_pos - ro;
-        let dist = to_sprite.length();
-        if dist <= 1e-4 {
-            return true;
-        }
 
-        let rd = to_sprite / dist;
+// Nearest surface crossed by the segment p → p + step (particles themselves are ignored)
+fn first_hit(scene: &Scene, p: Vector3, step: Vector3) -> Option<Hit> {
+    let len = step.length();
+    if len < 1e-6 {
+        return None;
+    }
+    let hit = scene.intersect_objects(&p, &(step / len), 0.0);
+    (hit.is_intersecting && hit.distance <= len).then_some(hit)
+}
 
-        let mut closest = Hit::no_hit();
-        for (obj_id, obj) in objects.iter().enumerate() {
-            
-            let h = obj.ray_intersect(&ro, &rd, obj_id);
-            if h.is_intersecting && h.distance < closest.distance {
-                 if h.material.transparency < 0.5 {
-                    closest = h;
-                }
+fn collide(s: &mut Sprite, collision: Collision, hit: &Hit) {
+    // keep the particle just off the surface, on the side it came from
+    let n = if hit.normal.dot(s.velocity) > 0.0 { -hit.normal } else { hit.normal };
+    let rest = hit.point + n * 1e-3;
+    match collision {
+        Collision::None => {}
+        Collision::Bounce { restitution, friction } => {
+            let vn = n * s.velocity.dot(n);
+            let vt = s.velocity - vn;
+            s.velocity = vt * (1.0 - friction) - vn * restitution;
+            s.position = rest;
+            // too slow to bounce again: it comes to rest
+            if s.velocity.length() < 0.05 {
+                s.velocity = Vector3::zero();
+                s.stuck = true;
             }
         }
-
-        // If no hit, sprite is visible
-        if !closest.is_intersecting {
-            return true;
-        }
-
-        // If closest hit is farther than sprite, sprite is in front
-        closest.distance >= dist - 1e-3
-}
-
-fn respawn(rmin: Vector3, rmax: Vector3, tex_id: char) -> Sprite {
-        let mut rng = rand::thread_rng();
-
-        let rx = rng.r#gen::<f32>();
-        let ry = rng.r#gen::<f32>();
-        let rz = rng.r#gen::<f32>();
-
-        let pos = Vector3::new(
-            rmin.x + (rmax.x - rmin.x) * rx,
-            rmin.y + (rmax.y - rmin.y) * ry,
-            rmin.z + (rmax.z - rmin.z) * rz,
-        );
-
-        let vx = (rng.r#gen::<f32>() - 0.5) * 2.0;
-        let vy = (rng.r#gen::<f32>() - 0.5) * 2.0;
-        let vz = (rng.r#gen::<f32>() - 0.5) * 2.0;
-        let vel = Vector3::new(vx, vy, vz) * 0.5;
-
-        let lifetime = rng.gen_range(1.0..4.0);
-
-        Sprite {
-            position: pos,
-            velocity: vel,
-            size_world: 0.2,
-            age: 0.0,
-            lifetime,
-            tex_id,
-            visible: true,
+        Collision::Stick => {
+            s.position = rest;
+            s.velocity = Vector3::zero();
+            s.stuck = true;
         }
+        Collision::Die => s.lifetime = 0.0,
     }
-
+}
 
 pub fn project_to_screen(
     camera: &Camera,
     p_world: &Vector3,
     width: f32,
     height: f32,
-    fov: f32,
 ) -> Option<(i32, i32, f32)> {
     let aspect = width / height;
-    let perspective_scale = (fov * 0.5).tan();
-
-    let p_cam = camera.world_to_camera(p_world);
-
-    // Behind camera?
-    if p_cam.z >= 0.0 {
-        return None;
-    }
-
-    // NDC coordinates
-    let x_ndc = (p_cam.x / -p_cam.z) / (aspect * perspective_scale);
-    let y_ndc = (p_cam.y / -p_cam.z) / (perspective_scale);
 
-    if x_ndc < -1.0 || x_ndc > 1.0 || y_ndc < -1.0 || y_ndc > 1.0 {
-        return None;
-    }
+    // NDC coordinates (honours the camera projection)
+    let (x_ndc, y_ndc, depth) = camera.project(p_world, aspect)?;
 
     let sx = ((x_ndc + 1.0) * 0.5) * width;
     let sy = ((1.0 - y_ndc) * 0.5) * height;
 
-    Some((sx as i32, sy as i32, -p_cam.z))
+    Some((sx as i32, sy as i32, depth))
 }
 
+/// Composites sprites over the traced frame: sorted back to front, depth-tested per pixel
+/// against the framebuffer depth and alpha-blended with their tint.
 pub fn render_sprites(
     framebuffer: &mut Framebuffer,
     sprites: &[Sprite],
     camera: &Camera,
     texmgr: &TextureManager,
-    fov: f32,
 ) {
     let width = framebuffer.width as f32;
     let height = framebuffer.height as f32;
 
-    for s in sprites {
-        if !s.visible {
+    // With the shutter open each sprite is stamped at several instants, leaving a streak along its velocity
+    let stamps = if camera.shutter > 0.0 { camera.time_samples.clamp(1, 8) } else { 1 };
+
+    // Far sprites first so nearer ones blend over them
+    let mut order: Vec<(f32, &Sprite)> = sprites
+        .iter()
+        .filter(|s| s.visible)
+        .map(|s| (camera.view_depth(&s.position), s))
+        .collect();
+    order.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    for (_, s) in order {
+        let Some((tw, th)) = texmgr.size(s.tex_id) else {
             continue;
-        }
+        };
+        let (tw, th) = (tw as f32, th as f32);
+        let tint_alpha = s.tint.a as f32 / 255.0 / stamps as f32;
+
+        for i in 0..stamps {
+            let time = if stamps > 1 { camera.shutter * i as f32 / (stamps - 1) as f32 } else { 0.0 };
+            let cam = camera.at_time(time);
+            let p = s.position + s.velocity * time;
 
-        if let Some((sx, sy, depth)) = project_to_screen(camera, &s.position, width, height, fov) {
+            let Some((sx, sy, depth)) = project_to_screen(&cam, &p, width, height) else {
+                continue;
+            };
             // approximate size in pixels from world size + depth
-            let perspective_scale = (fov * 0.5).tan();
-            let pixel_size = (s.size_world / depth)
-                * (height / (2.0 * perspective_scale)); // rough: world→screen
+            let pixel_size = s.size_world * cam.pixels_per_unit(depth, height); // rough: world→screen
 
             let half = pixel_size as i32 / 2;
-            let min_x = (sx - half).max(0);
+            let (x0, y0) = (sx - half, sy - half);
+            let size = (2 * half + 1) as f32;
+            let min_x = x0.max(0);
             let max_x = (sx + half).min(framebuffer.width as i32 - 1);
-            let min_y = (sy - half).max(0);
+            let min_y = y0.max(0);
             let max_y = (sy + half).min(framebuffer.height as i32 - 1);
 
-            let tex = texmgr.get_texture(s.tex_id);
-            if tex.is_none() { continue; }
-            let tex = tex.unwrap();
-            let tw = tex.width as f32;
-            let th = tex.height as f32;
-
             for py in min_y..=max_y {
                 for px in min_x..=max_x {
-                    // local sprite UV
-                    let u = (px - min_x) as f32 / (max_x - min_x + 1) as f32;
-                    let v = (py - min_y) as f32 / (max_y - min_y + 1) as f32;
+                    // Hidden behind the traced scene at this pixel
+                    if !framebuffer.depth_test(px as u32, py as u32, depth) {
+                        continue;
+                    }
+
+                    // local sprite UV (relative to the unclipped quad, so clipped sprites keep their texels)
+                    let u = (px - x0) as f32 / size;
+                    let v = (py - y0) as f32 / size;
 
                     let tx = (u * (tw - 1.0)) as u32;
                     let ty = (v * (th - 1.0)) as u32;
 
-                    let mut col = texmgr.get_pixel_color(s.tex_id, tx, ty);
-
-                    // Simple alpha test (no blending): skip transparent pixels
-                    if col.a == 0 {
+                    let texel = texmgr.get_pixel_color(s.tex_id, tx, ty);
+                    let alpha = texel.a as f32 / 255.0 * tint_alpha;
+                    if alpha <= 0.0 {
                         continue;
-                    } else {
-                        col = Color::MAGENTA;
                     }
 
-                    framebuffer.set_current_color(col);
-                    framebuffer.set_pixel(px as u32, py as u32);
+                    let col = Color::new(
+                        (texel.r as u16 * s.tint.r as u16 / 255) as u8,
+                        (texel.g as u16 * s.tint.g as u16 / 255) as u8,
+                        (texel.b as u16 * s.tint.b as u16 / 255) as u8,
+                        255,
+                    );
+                    framebuffer.blend_pixel(px as u32, py as u32, col, alpha);
                 }
             }
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,195 @@
+// temporal.rs
+use raylib::prelude::*;
+
+use crate::{
+    aov::{AovBuffer, AovSample},
+    camera::{Camera, Projection},
+    integrator::Integrator,
+    occlusion::AoMode,
+    scene::Scene,
+};
+
+// -------- Reproyección temporal --------
+
+const NORMAL_TOLERANCE: f32 = 0.9; // minimum n·n' between the two frames
+const DEPTH_TOLERANCE: f32 = 0.02; // allowed position change, relative to the hit distance
+const SKY_DISTANCE: f32 = 1.0e4;   // sky pixels are reprojected by direction, as a far point
+
+// Settings that change what a pixel looks like without moving anything: any change flushes the cache.
+// Reprojection only follows the pinhole ray, so the lens, shutter, fov and projection count too
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct CacheKey {
+    integrator: &'static str,
+    ao: AoMode,
+    voxel_lighting: bool,
+    raytraced_particles: bool,
+    revision: u64,
+    lens: Option<(f32, f32, u32, u32)>, // aperture, focal distance, blades, samples; None for a pinhole
+    shutter: Option<(f32, u32)>,        // shutter, samples; None without motion blur
+    fov: f32,
+    projection: Projection,
+}
+
+impl CacheKey {
+    fn new(scene: &Scene, integrator: &dyn Integrator, camera: &Camera) -> Self {
+        CacheKey {
+            integrator: integrator.name(),
+            ao: scene.ao,
+            voxel_lighting: scene.voxel_lighting,
+            raytraced_particles: scene.raytraced_particles,
+            revision: scene.revision(),
+            lens: (camera.aperture > 0.0)
+                .then_some((camera.aperture, camera.focal_distance, camera.blade_count, camera.lens_samples)),
+            shutter: (camera.shutter > 0.0).then_some((camera.shutter, camera.time_samples)),
+            fov: camera.fov,
+            projection: camera.projection,
+        }
+    }
+}
+
+/// Shaded pixels of the previous frame, reused where the same surface is still visible.
+/// Each pixel's primary hit is reprojected into the previous camera; if the old pixel saw the
+/// same object at the same depth with the same normal, its color is kept and only the
+/// (cheap) primary ray is traced. Rejected pixels, animated textures, reflections and refraction
+/// seen from a moving eye, plus a rotating 1/`refresh_period` of the image, are traced in full,
+/// so moving lights, particles and the sky still catch up.
+pub struct TemporalCache {
+    pub enabled: bool,
+    pub refresh_period: u32, // every pixel is re-traced at least once every this many frames
+
+    color: Vec<Vector3>, // linear, before denoising
+    gbuffer: Option<AovBuffer>,
+    camera: Option<Camera>,
+    key: Option<CacheKey>,
+    frame: u32,
+}
+
+impl Default for TemporalCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TemporalCache {
+    pub fn new() -> Self {
+        TemporalCache {
+            enabled: false,
+            refresh_period: 8,
+            color: Vec::new(),
+            gbuffer: None,
+            camera: None,
+            key: None,
+            frame: 0,
+        }
+    }
+
+    /// Forgets the previous frame.
+    pub fn clear(&mut self) {
+        self.color.clear();
+        self.gbuffer = None;
+        self.camera = None;
+        self.key = None;
+    }
+
+    /// Starts a frame: flushes the history if the scene, the shading settings or the camera's
+    /// lens, shutter, fov or projection changed. Returns whether there is anything to reuse.
+    pub fn begin_frame(&mut self, scene: &Scene, integrator: &dyn Integrator, camera: &Camera) -> bool {
+        if !self.enabled {
+            self.clear();
+            return false;
+        }
+        let key = CacheKey::new(scene, integrator, camera);
+        if self.key != Some(key) {
+            self.clear();
+            self.key = Some(key);
+        }
+        self.frame = self.frame.wrapping_add(1);
+        self.gbuffer.is_some()
+    }
+
+    /// True if (x, y) is in this frame's share of forced re-traces.
+    pub fn due(&self, x: u32, y: u32) -> bool {
+        let period = self.refresh_period.max(1);
+        // diagonal interleave: neighbours are refreshed on different frames
+        x.wrapping_mul(3).wrapping_add(y.wrapping_mul(5)).wrapping_add(self.frame).is_multiple_of(period)
+    }
+
+    /// Cached color and first-hit sample for the pinhole ray through (`ndc_x`, `ndc_y`),
+    /// or None if the pixel has to be traced.
+    pub fn lookup(&self, scene: &Scene, camera: &Camera, ndc_x: f32, ndc_y: f32, aspect: f32) -> Option<(Vector3, AovSample)> {
+        let (prev, prev_camera) = self.gbuffer.as_ref().zip(self.camera.as_ref())?;
+        let (ro, rd) = camera.primary_ray(ndc_x, ndc_y, aspect, Vector2::zero())?;
+        let hit = scene.intersect(&ro, &rd, 0.0);
+
+        if !hit.is_intersecting {
+            // the sky only depends on the direction
+            let j = prev_pixel(prev, prev_camera, &(prev_camera.eye + rd * SKY_DISTANCE))?;
+            return prev.samples[j].is_sky().then(|| (self.color[j], AovSample::sky()));
+        }
+
+        // animated textures change every frame; mirrors and glass change whenever the eye moves
+        let m = hit.material;
+        let view_dependent = m.reflectivity > 0.0 || m.transparency > 0.0;
+        if m.anim_frames > 1 || (view_dependent && camera.eye != prev_camera.eye) {
+            return None;
+        }
+
+        let mut sample = AovSample {
+            depth: hit.distance,
+            position: hit.point,
+            normal: hit.normal,
+            obj_id: Some(hit.obj_id),
+            ..AovSample::sky()
+        };
+        let j = reproject(&sample, prev, prev_camera)?;
+        let old = &prev.samples[j];
+        if old.obj_id != sample.obj_id {
+            return None;
+        }
+        // the rest of the passes (albedo, lighting) come along with the color
+        sample = AovSample { depth: sample.depth, position: sample.position, normal: sample.normal, ..*old };
+        Some((self.color[j], sample))
+    }
+
+    /// Keeps this frame (raw linear color and its first hits) for the next one.
+    pub fn store(&mut self, color: &[Vector3], gbuffer: &AovBuffer, camera: &Camera) {
+        if !self.enabled {
+            return;
+        }
+        self.color.clear();
+        self.color.extend_from_slice(color);
+        match &mut self.gbuffer {
+            Some(g) => g.clone_from(gbuffer),
+            None => self.gbuffer = Some(gbuffer.clone()),
+        }
+        self.camera = Some(camera.clone());
+    }
+}
+
+/// Previous-frame pixel showing the same surface as `g`, if it was visible there:
+/// rejects disocclusions (the old pixel saw something at another depth) and normal changes.
+/// The two frames may have different sizes.
+pub fn reproject(g: &AovSample, prev: &AovBuffer, prev_camera: &Camera) -> Option<usize> {
+    let j = prev_pixel(prev, prev_camera, &g.position)?;
+    let pg = &prev.samples[j];
+    if pg.is_sky() || pg.normal.dot(g.normal) < NORMAL_TOLERANCE {
+        return None;
+    }
+    // disocclusion: the old pixel saw something else
+    if (pg.position - g.position).length() > DEPTH_TOLERANCE * g.depth + DEPTH_TOLERANCE {
+        return None;
+    }
+    Some(j)
+}
+
+// Pixel of `prev` where `prev_camera` saw the world point `p`
+fn prev_pixel(prev: &AovBuffer, prev_camera: &Camera, p: &Vector3) -> Option<usize> {
+    let aspect = prev.width as f32 / prev.height as f32;
+    let (x_ndc, y_ndc, _) = prev_camera.project(p, aspect)?;
+    let px = ((x_ndc + 1.0) * 0.5 * prev.width as f32).round() as i32;
+    let py = ((1.0 - y_ndc) * 0.5 * prev.height as f32).round() as i32;
+    if px < 0 || py < 0 || px >= prev.width as i32 || py >= prev.height as i32 {
+        return None;
+    }
+    Some(py as usize * prev.width + px as usize)
+}
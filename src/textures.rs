@@ -4,50 +4,79 @@ use raylib::prelude::*;
 use std::collections::HashMap;
 use std::slice;
 
+// Map characters to texture file paths
+const TEXTURE_FILES: [(char, &str); 17] = [
+    ('+', "assets/stone.png"),
+    ('-', "assets/obsidian.png"),
+    ('g', "assets/moss_block.png"),
+    ('|', "assets/grass_block_side.png"),
+    ('#', "assets/dirt.png"), // default/fallback
+    ('t', "assets/glass.png"),
+    ('l', "assets/redstone_lamp_on.png"),
+    ('p', "assets/nether_portal.png"),
+    ('0', "assets/portal/generic_0.png"),
+    ('1', "assets/portal/generic_1.png"),
+    ('2', "assets/portal/generic_2.png"),
+    ('3', "assets/portal/generic_3.png"),
+    ('4', "assets/portal/generic_4.png"),
+    ('5', "assets/portal/generic_5.png"),
+    ('6', "assets/portal/generic_6.png"),
+    ('7', "assets/portal/generic_7.png"),
+    ('L', "assets/oak_leaves.png"),
+];
+
 pub struct TextureManager {
     images: HashMap<char, Image>,       // Store images for pixel access
-    textures: HashMap<char, Texture2D>, // Store GPU textures for rendering
+    #[cfg(feature = "viewer")]
+    textures: HashMap<char, Texture2D>, // GPU textures, only when created with `new` (viewer)
 }
 
 impl TextureManager {
-    pub fn new(rl: &mut RaylibHandle, thread: &RaylibThread) -> Self {
+    /// No textures at all: every lookup falls back to white (for tests).
+    #[cfg(test)]
+    pub(crate) fn empty() -> Self {
+        TextureManager {
+            images: HashMap::new(),
+            #[cfg(feature = "viewer")]
+            textures: HashMap::new(),
+        }
+    }
+
+    /// Loads the images only (CPU side): enough to trace, no window needed.
+    pub fn load() -> Self {
         let mut images = HashMap::new();
-        let mut textures = HashMap::new();
-
-        // Map characters to texture file paths
-        let texture_files = vec![
-            ('+', "assets/stone.png"),
-            ('-', "assets/obsidian.png"),
-            ('g', "assets/moss_block.png"),
-            ('|', "assets/grass_block_side.png"),
-            ('#', "assets/dirt.png"), // default/fallback
-            ('t', "assets/glass.png"),
-            ('l', "assets/redstone_lamp_on.png"),
-            ('p', "assets/nether_portal.png"),
-            ('0', "assets/portal/generic_0.png"),
-            ('1', "assets/portal/generic_1.png"),
-            ('2', "assets/portal/generic_2.png"),
-            ('3', "assets/portal/generic_3.png"),
-            ('4', "assets/portal/generic_4.png"),
-            ('5', "assets/portal/generic_5.png"),
-            ('6', "assets/portal/generic_6.png"),
-            ('7', "assets/portal/generic_7.png"),
-        ];
-
-        for (ch, path) in texture_files {
-            let mut image = Image::load_image(path).expect(&format!("Failed to load image {}", path));
+        for (ch, path) in TEXTURE_FILES {
+            let mut image = Image::load_image(path).unwrap_or_else(|e| panic!("Failed to load image {}: {}", path, e));
 
             // Force a known layout: UNCOMPRESSED_R8G8B8A8
             image.set_format(PixelFormat::PIXELFORMAT_UNCOMPRESSED_R8G8B8A8);
 
             // Optional, if your UVs expect flipped V:
             // image.flip_vertical();
-            let texture = rl.load_texture(thread, path).expect(&format!("Failed to load texture {}", path));
             images.insert(ch, image);
-            textures.insert(ch, texture);
         }
 
-        TextureManager { images, textures }
+        TextureManager {
+            images,
+            #[cfg(feature = "viewer")]
+            textures: HashMap::new(),
+        }
+    }
+
+    /// Loads the images and uploads them as GPU textures for the viewer.
+    #[cfg(feature = "viewer")]
+    pub fn new(rl: &mut RaylibHandle, thread: &RaylibThread) -> Self {
+        let mut manager = Self::load();
+        for (ch, path) in TEXTURE_FILES {
+            let texture = rl.load_texture(thread, path).unwrap_or_else(|e| panic!("Failed to load texture {}: {}", path, e));
+            manager.textures.insert(ch, texture);
+        }
+        manager
+    }
+
+    /// Width and height of a texture, in texels.
+    pub fn size(&self, ch: char) -> Option<(u32, u32)> {
+        self.images.get(&ch).map(|img| (img.width.max(1) as u32, img.height.max(1) as u32))
     }
 
     pub fn get_pixel_color(&self, ch: char, tx: u32, ty: u32) -> Color {
@@ -60,6 +89,7 @@ impl TextureManager {
         }
     }
 
+    #[cfg(feature = "viewer")]
     pub fn get_texture(&self, ch: char) -> Option<&Texture2D> {
         self.textures.get(&ch)
     }
@@ -80,8 +110,8 @@ impl TextureManager {
     }
     pub fn sample_uv_bilinear(&self, ch: char, u: f32, v: f32) -> Color {
         if let Some(image) = self.images.get(&ch) {
-            let w = image.width as i32;
-            let h = image.height as i32;
+            let w = image.width;
+            let h = image.height;
 
             // Wrap and convert to pixel space (centered on pixel centers)
             let uu = ((u % 1.0) + 1.0) % 1.0;
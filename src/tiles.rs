@@ -0,0 +1,189 @@
+// tiles.rs
+use std::time::Duration;
+
+// -------- Tiles --------
+
+/// Order the tiles of a frame are handed to the workers (and show up on screen).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TileOrder {
+    Scanline, // left to right, top to bottom
+    Spiral,   // from the centre outwards, where the eye usually is
+    Hilbert,  // along a Hilbert curve: neighbouring tiles run together, friendlier to caches
+}
+
+impl TileOrder {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "scanline" => Some(TileOrder::Scanline),
+            "spiral" => Some(TileOrder::Spiral),
+            "hilbert" => Some(TileOrder::Hilbert),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TileOrder::Scanline => "scanline",
+            TileOrder::Spiral => "spiral",
+            TileOrder::Hilbert => "hilbert",
+        }
+    }
+}
+
+/// A rectangle of the framebuffer, in pixels (edge tiles can be smaller than the tile size).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Tile {
+    pub fn len(&self) -> usize {
+        (self.width * self.height) as usize
+    }
+}
+
+/// Splits a `width`×`height` frame into `size`×`size` tiles, in `order`.
+pub fn tiles(width: u32, height: u32, size: u32, order: TileOrder) -> Vec<Tile> {
+    let size = size.max(1);
+    let (nx, ny) = (width.div_ceil(size), height.div_ceil(size));
+    let mut grid: Vec<(u32, u32)> = (0..ny).flat_map(|ty| (0..nx).map(move |tx| (tx, ty))).collect();
+
+    match order {
+        TileOrder::Scanline => {}
+        TileOrder::Spiral => {
+            // rings of tiles around the centre, each ring swept by angle
+            let (cx, cy) = (width as f32 * 0.5, height as f32 * 0.5);
+            let key = |&(tx, ty): &(u32, u32)| {
+                let dx = (tx as f32 + 0.5) * size as f32 - cx;
+                let dy = (ty as f32 + 0.5) * size as f32 - cy;
+                ((dx.abs().max(dy.abs()) / size as f32).round(), dy.atan2(dx))
+            };
+            grid.sort_by(|a, b| {
+                let (ka, kb) = (key(a), key(b));
+                ka.0.total_cmp(&kb.0).then(ka.1.total_cmp(&kb.1))
+            });
+        }
+        TileOrder::Hilbert => {
+            let n = nx.max(ny).next_power_of_two();
+            grid.sort_by_key(|&(tx, ty)| hilbert_index(n, tx, ty));
+        }
+    }
+
+    grid.into_iter()
+        .map(|(tx, ty)| {
+            let (x, y) = (tx * size, ty * size);
+            Tile { x, y, width: size.min(width - x), height: size.min(height - y) }
+        })
+        .collect()
+}
+
+// Distance along the Hilbert curve filling an n×n grid (n a power of two)
+fn hilbert_index(n: u32, mut x: u32, mut y: u32) -> u64 {
+    let mut d = 0u64;
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = (x & s > 0) as u32;
+        let ry = (y & s > 0) as u32;
+        d += s as u64 * s as u64 * ((3 * rx) ^ ry) as u64;
+        // rotate the quadrant so the curve stays continuous
+        if ry == 0 {
+            if rx == 1 {
+                x = n - 1 - x;
+                y = n - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    d
+}
+
+// -------- Progreso --------
+
+/// How far a frame has got: passed to the `Renderer::render_progressive` callback.
+#[derive(Clone, Copy, Debug)]
+pub struct Progress {
+    pub tiles_done: usize,
+    pub tiles_total: usize,
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    pub fn fraction(&self) -> f32 {
+        if self.tiles_total > 0 { self.tiles_done as f32 / self.tiles_total as f32 } else { 1.0 }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.tiles_done >= self.tiles_total
+    }
+
+    /// Time left if the remaining tiles go as fast as the finished ones.
+    pub fn eta(&self) -> Option<Duration> {
+        let f = self.fraction();
+        (f > 0.0).then(|| self.elapsed.mul_f32((1.0 - f) / f))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every pixel is in exactly one tile, whatever the order
+    fn assert_covers(width: u32, height: u32, size: u32, order: TileOrder) {
+        let mut seen = vec![0u8; (width * height) as usize];
+        for t in tiles(width, height, size, order) {
+            assert!(t.width > 0 && t.height > 0 && t.width <= size && t.height <= size);
+            for y in t.y..t.y + t.height {
+                for x in t.x..t.x + t.width {
+                    seen[(y * width + x) as usize] += 1;
+                }
+            }
+        }
+        assert!(seen.iter().all(|&n| n == 1), "{:?} {}x{} / {}", order, width, height, size);
+    }
+
+    #[test]
+    fn tiles_cover_the_frame_once() {
+        for order in [TileOrder::Scanline, TileOrder::Spiral, TileOrder::Hilbert] {
+            assert_covers(64, 64, 16, order);
+            assert_covers(100, 37, 16, order); // ragged edges
+            assert_covers(5, 3, 16, order);    // smaller than a tile
+        }
+    }
+
+    #[test]
+    fn hilbert_index_visits_neighbours_in_turn() {
+        for n in [2, 4, 8, 16] {
+            let mut cells = vec![(0, 0); (n * n) as usize];
+            for y in 0..n {
+                for x in 0..n {
+                    cells[hilbert_index(n, x, y) as usize] = (x, y);
+                }
+            }
+            // a bijection onto 0..n², and consecutive cells share an edge
+            for w in cells.windows(2) {
+                let ((x0, y0), (x1, y1)) = (w[0], w[1]);
+                assert_eq!(x0.abs_diff(x1) + y0.abs_diff(y1), 1, "n {}: {:?}", n, w);
+            }
+        }
+    }
+
+    #[test]
+    fn hilbert_tiles_step_to_adjacent_tiles() {
+        let order = tiles(128, 128, 16, TileOrder::Hilbert);
+        for w in order.windows(2) {
+            let step = w[0].x.abs_diff(w[1].x) + w[0].y.abs_diff(w[1].y);
+            assert_eq!(step, 16, "{:?}", w);
+        }
+    }
+
+    #[test]
+    fn spiral_starts_at_the_centre() {
+        let first = tiles(160, 160, 16, TileOrder::Spiral)[0];
+        let (cx, cy) = (first.x + first.width / 2, first.y + first.height / 2);
+        assert!(cx.abs_diff(80) <= 16 && cy.abs_diff(80) <= 16, "{:?}", first);
+    }
+}
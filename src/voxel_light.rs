@@ -0,0 +1,351 @@
+// voxel_light.rs
+use std::collections::VecDeque;
+
+use raylib::prelude::*;
+
+use crate::{
+    object::Object,
+    occlusion::unit_cell,
+    ray_intersect::ObjectId,
+};
+
+// -------- Niveles de luz por bloque (estilo Minecraft) --------
+
+pub const MAX_LIGHT: u8 = 15;
+const MARGIN: i32 = MAX_LIGHT as i32 + 1; // light can't spread further than this past the blocks
+const LAMP_EMISSION: f32 = 3.0;           // emission_strength that maps to level 15
+const BLOCK_LIGHT_COLOR: Vector3 = Vector3 { x: 1.0, y: 0.88, z: 0.7 }; // warm, like torches
+const BLOCK_LIGHT_STRENGTH: f32 = 1.5;
+
+const DIRS: [[i32; 3]; 6] = [[1, 0, 0], [-1, 0, 0], [0, 1, 0], [0, -1, 0], [0, 0, 1], [0, 0, -1]];
+const DOWN: [i32; 3] = [0, -1, 0];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Channel {
+    Block, // from emissive blocks
+    Sky,   // from above
+}
+
+/// Block and sky light levels (0–15) flood-filled over the unit-block grid. Opaque blocks stop
+/// light, emissive blocks are sources, and sky light falls straight down at full level until it
+/// hits something. `set_block` updates the levels incrementally.
+#[derive(Clone, Debug, Default)]
+pub struct LightGrid {
+    min: [i32; 3],
+    size: [i32; 3],
+    opaque: Vec<bool>,
+    emission: Vec<u8>,
+    block: Vec<u8>,
+    sky: Vec<u8>,
+}
+
+impl LightGrid {
+    /// Grid around every unit `Cube`, lit from scratch.
+    pub fn from_objects<'a>(objects: impl IntoIterator<Item = (ObjectId, &'a Object)>) -> Self {
+        let blocks: Vec<_> = objects.into_iter().filter_map(|(_, obj)| block_of(obj)).collect();
+        let Some(&(first, ..)) = blocks.first() else {
+            return LightGrid::default();
+        };
+
+        let (mut lo, mut hi) = (first, first);
+        for &(c, ..) in &blocks {
+            for a in 0..3 {
+                lo[a] = lo[a].min(c[a]);
+                hi[a] = hi[a].max(c[a]);
+            }
+        }
+        let min = [lo[0] - MARGIN, lo[1] - MARGIN, lo[2] - MARGIN];
+        let size = [hi[0] - lo[0] + 1 + 2 * MARGIN, hi[1] - lo[1] + 1 + 2 * MARGIN, hi[2] - lo[2] + 1 + 2 * MARGIN];
+        let n = (size[0] * size[1] * size[2]) as usize;
+        let mut grid = LightGrid {
+            min,
+            size,
+            opaque: vec![false; n],
+            emission: vec![0; n],
+            block: vec![0; n],
+            sky: vec![0; n],
+        };
+        for (c, opaque, emission) in blocks {
+            let i = grid.index(c).expect("blocks are inside the grid");
+            grid.opaque[i] = opaque;
+            grid.emission[i] = emission;
+        }
+        grid.relight_all();
+        grid
+    }
+
+    /// (block, sky) levels of a cell; outside the grid there is open sky and no block light.
+    pub fn levels(&self, cell: [i32; 3]) -> (u8, u8) {
+        match self.index(cell) {
+            Some(i) => (self.block[i], self.sky[i]),
+            None => (0, MAX_LIGHT),
+        }
+    }
+
+    /// Changes one cell and re-lights only what it affects. Returns false if the cell is outside
+    /// the grid (the caller has to rebuild it with `from_objects`).
+    pub fn set_block(&mut self, cell: [i32; 3], opaque: bool, emission: u8) -> bool {
+        let Some(i) = self.index(cell) else {
+            return false;
+        };
+        self.opaque[i] = opaque;
+        self.emission[i] = emission;
+
+        for ch in [Channel::Block, Channel::Sky] {
+            let old = self.get(ch, cell);
+            let mut relight = if old > 0 { self.unlight(ch, cell, old) } else { VecDeque::new() };
+            if !opaque {
+                // the neighbours spread back into the cell
+                relight.extend(DIRS.iter().map(|d| add(cell, *d)).filter(|n| self.index(*n).is_some()));
+                if ch == Channel::Sky && cell[1] == self.min[1] + self.size[1] - 1 {
+                    self.set(ch, cell, MAX_LIGHT);
+                    relight.push_back(cell);
+                }
+            }
+            if ch == Channel::Block && emission > 0 {
+                self.set(ch, cell, emission);
+                relight.push_back(cell);
+            }
+            self.spread(ch, relight);
+        }
+        true
+    }
+
+    /// Ambient light at `p` (a point just in front of a surface): sky light scaled by the sky's
+    /// ambient level plus warm block light. Levels are blended trilinearly between cell centres,
+    /// skipping opaque cells, so faces get Minecraft-like smooth lighting.
+    pub fn ambient(&self, p: Vector3, sky_ambient: f32) -> Vector3 {
+        let q = p - Vector3::one() * 0.5;
+        let base = [q.x.floor() as i32, q.y.floor() as i32, q.z.floor() as i32];
+        let f = [q.x - base[0] as f32, q.y - base[1] as f32, q.z - base[2] as f32];
+
+        let (mut sum_w, mut block, mut sky) = (0.0, 0.0, 0.0);
+        for corner in 0..8 {
+            let o = [corner & 1, (corner >> 1) & 1, (corner >> 2) & 1];
+            let c = [base[0] + o[0], base[1] + o[1], base[2] + o[2]];
+            if self.index(c).is_some_and(|i| self.opaque[i]) {
+                continue;
+            }
+            let w: f32 = (0..3).map(|a| if o[a] == 1 { f[a] } else { 1.0 - f[a] }).product();
+            let (b, s) = self.levels(c);
+            sum_w += w;
+            block += w * brightness(b);
+            sky += w * brightness(s);
+        }
+        if sum_w <= 0.0 {
+            let (b, s) = self.levels([p.x.floor() as i32, p.y.floor() as i32, p.z.floor() as i32]);
+            (block, sky, sum_w) = (brightness(b), brightness(s), 1.0);
+        }
+        Vector3::one() * (sky_ambient * sky / sum_w) + BLOCK_LIGHT_COLOR * (BLOCK_LIGHT_STRENGTH * block / sum_w)
+    }
+
+    // -------- Flood fill --------
+
+    fn relight_all(&mut self) {
+        self.block.iter_mut().for_each(|l| *l = 0);
+        self.sky.iter_mut().for_each(|l| *l = 0);
+
+        let mut sources = VecDeque::new();
+        for i in 0..self.emission.len() {
+            if self.emission[i] > 0 {
+                self.block[i] = self.emission[i];
+                sources.push_back(self.cell(i));
+            }
+        }
+        self.spread(Channel::Block, sources);
+
+        // the top layer is open to the sky
+        let mut sources = VecDeque::new();
+        let top = self.min[1] + self.size[1] - 1;
+        for x in self.min[0]..self.min[0] + self.size[0] {
+            for z in self.min[2]..self.min[2] + self.size[2] {
+                let c = [x, top, z];
+                if !self.is_opaque(c) {
+                    self.set(Channel::Sky, c, MAX_LIGHT);
+                    sources.push_back(c);
+                }
+            }
+        }
+        self.spread(Channel::Sky, sources);
+    }
+
+    // BFS: every lit cell in the queue passes its level on to brighter-able neighbours
+    fn spread(&mut self, ch: Channel, mut queue: VecDeque<[i32; 3]>) {
+        while let Some(c) = queue.pop_front() {
+            let level = self.get(ch, c);
+            if level == 0 {
+                continue;
+            }
+            for d in DIRS {
+                let n = add(c, d);
+                let Some(i) = self.index(n) else { continue };
+                if self.opaque[i] {
+                    continue;
+                }
+                let passed = falloff(ch, level, d);
+                if passed > self.get(ch, n) {
+                    self.set(ch, n, passed);
+                    queue.push_back(n);
+                }
+            }
+        }
+    }
+
+    // Removes the light that came through `start` (which had `old`); returns the cells at the
+    // edge of the darkened region, which still have light of their own to spread back
+    fn unlight(&mut self, ch: Channel, start: [i32; 3], old: u8) -> VecDeque<[i32; 3]> {
+        let mut remove = VecDeque::from([(start, old)]);
+        let mut relight = VecDeque::new();
+        self.set(ch, start, 0);
+        while let Some((c, level)) = remove.pop_front() {
+            for d in DIRS {
+                let n = add(c, d);
+                let Some(i) = self.index(n) else { continue };
+                let nl = self.get(ch, n);
+                if nl == 0 {
+                    continue;
+                }
+                if nl <= falloff(ch, level, d) {
+                    // lit through `c`: goes dark too (sources light up again)
+                    self.set(ch, n, 0);
+                    remove.push_back((n, nl));
+                    if ch == Channel::Block && self.emission[i] > 0 {
+                        self.block[i] = self.emission[i];
+                        relight.push_back(n);
+                    }
+                } else {
+                    relight.push_back(n);
+                }
+            }
+        }
+        relight
+    }
+
+    // -------- Celdas --------
+
+    fn index(&self, c: [i32; 3]) -> Option<usize> {
+        let [x, y, z] = [c[0] - self.min[0], c[1] - self.min[1], c[2] - self.min[2]];
+        if x < 0 || y < 0 || z < 0 || x >= self.size[0] || y >= self.size[1] || z >= self.size[2] {
+            return None;
+        }
+        Some(((y * self.size[2] + z) * self.size[0] + x) as usize)
+    }
+
+    fn cell(&self, i: usize) -> [i32; 3] {
+        let i = i as i32;
+        let x = i % self.size[0];
+        let z = (i / self.size[0]) % self.size[2];
+        let y = i / (self.size[0] * self.size[2]);
+        [x + self.min[0], y + self.min[1], z + self.min[2]]
+    }
+
+    fn is_opaque(&self, c: [i32; 3]) -> bool {
+        self.index(c).is_some_and(|i| self.opaque[i])
+    }
+
+    fn get(&self, ch: Channel, c: [i32; 3]) -> u8 {
+        let Some(i) = self.index(c) else { return 0 };
+        match ch {
+            Channel::Block => self.block[i],
+            Channel::Sky => self.sky[i],
+        }
+    }
+
+    fn set(&mut self, ch: Channel, c: [i32; 3], level: u8) {
+        let Some(i) = self.index(c) else { return };
+        match ch {
+            Channel::Block => self.block[i] = level,
+            Channel::Sky => self.sky[i] = level,
+        }
+    }
+}
+
+/// Grid cell, opacity and emitted level of an object, if it is a unit block.
+pub fn block_of(obj: &Object) -> Option<([i32; 3], bool, u8)> {
+    let Object::Cube(c) = obj else { return None };
+    let (x, y, z) = unit_cell(c.min, c.max)?;
+    let emission = (c.material.emission_strength / LAMP_EMISSION * MAX_LIGHT as f32).round().clamp(0.0, MAX_LIGHT as f32) as u8;
+    Some(([x, y, z], c.material.transparency <= 0.0, emission))
+}
+
+// Level a neighbour in direction `d` gets: one less, except full sky light falling straight down
+fn falloff(ch: Channel, level: u8, d: [i32; 3]) -> u8 {
+    if ch == Channel::Sky && level == MAX_LIGHT && d == DOWN { MAX_LIGHT } else { level.saturating_sub(1) }
+}
+
+// Minecraft's light curve: level 15 → 1.0, each step down loses roughly a fifth
+fn brightness(level: u8) -> f32 {
+    let f = level as f32 / MAX_LIGHT as f32;
+    f / (3.0 - 2.0 * f)
+}
+
+fn add(c: [i32; 3], d: [i32; 3]) -> [i32; 3] {
+    [c[0] + d[0], c[1] + d[1], c[2] + d[2]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::Cube;
+
+    // Two stone anchors fix the grid bounds, so incremental and rebuilt grids cover the same cells
+    fn scene_blocks() -> Vec<Cube> {
+        let mut cubes = vec![Cube::new(0.0, 0.0, 0.0, '+'), Cube::new(8.0, 4.0, 8.0, '+')];
+        for x in 2..=6 {
+            for z in 2..=6 {
+                cubes.push(Cube::new(x as f32, 3.0, z as f32, '+')); // roof
+            }
+        }
+        cubes.push(Cube::new(4.0, 1.0, 4.0, 'l')); // lamp under the roof
+        cubes
+    }
+
+    fn rebuilt(cubes: &[Cube]) -> LightGrid {
+        let objects: Vec<Object> = cubes.iter().map(|c| Object::Cube(*c)).collect();
+        LightGrid::from_objects(objects.iter().enumerate().map(|(i, o)| (ObjectId(i as u32), o)))
+    }
+
+    fn assert_same_levels(incremental: &LightGrid, full: &LightGrid) {
+        assert_eq!((incremental.min, incremental.size), (full.min, full.size));
+        for x in 0..full.size[0] {
+            for y in 0..full.size[1] {
+                for z in 0..full.size[2] {
+                    let c = add(full.min, [x, y, z]);
+                    assert_eq!(incremental.levels(c), full.levels(c), "cell {:?}", c);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn set_block_matches_a_full_relight() {
+        let mut cubes = scene_blocks();
+        let mut grid = rebuilt(&cubes);
+
+        // open a hole in the roof, put out the lamp, add glass and a lamp elsewhere
+        let edits = [
+            (Cube::new(4.0, 3.0, 4.0, '+'), false),
+            (Cube::new(4.0, 1.0, 4.0, 'l'), false),
+            (Cube::new(3.0, 2.0, 3.0, 't'), true),
+            (Cube::new(2.0, 0.0, 2.0, 'l'), true),
+            (Cube::new(4.0, 3.0, 4.0, '+'), true),
+        ];
+        for (cube, place) in edits {
+            let (cell, opaque, emission) = block_of(&Object::Cube(cube)).unwrap();
+            if place {
+                cubes.push(cube);
+                assert!(grid.set_block(cell, opaque, emission));
+            } else {
+                cubes.retain(|c| c.min != cube.min);
+                assert!(grid.set_block(cell, false, 0));
+            }
+            assert_same_levels(&grid, &rebuilt(&cubes));
+        }
+    }
+
+    #[test]
+    fn set_block_outside_the_grid_asks_for_a_rebuild() {
+        let mut grid = rebuilt(&scene_blocks());
+        assert!(!grid.set_block([100, 0, 0], true, 0));
+    }
+}